@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::util::{Direction, Point};
+
+pub struct Heightmap<'a> {
+    map: &'a HashMap<Point, u64>,
+}
+
+impl<'a> Heightmap<'a> {
+    pub fn new(map: &'a HashMap<Point, u64>) -> Self {
+        Self { map }
+    }
+
+    fn neighbors(&self, point: Point) -> impl Iterator<Item = (Point, u64)> + '_ {
+        Direction::all()
+            .map(move |dir| point + dir)
+            .filter_map(|next| self.map.get(&next).map(|&height| (next, height)))
+    }
+
+    pub fn height_range(&self) -> Option<(u64, u64)> {
+        let min = self.map.values().min()?;
+        let max = self.map.values().max()?;
+
+        Some((*min, *max))
+    }
+
+    // Cells strictly higher than every passable neighbor
+    pub fn local_maxima(&self) -> Vec<Point> {
+        self.filter_sorted(|point, height| self.neighbors(point).all(|(_, h)| h < height))
+    }
+
+    // Cells strictly lower than every passable neighbor
+    pub fn local_minima(&self) -> Vec<Point> {
+        self.filter_sorted(|point, height| self.neighbors(point).all(|(_, h)| h > height))
+    }
+
+    fn filter_sorted(&self, f: impl Fn(Point, u64) -> bool) -> Vec<Point> {
+        let mut points = self
+            .map
+            .iter()
+            .filter(|&(&point, &height)| f(point, height))
+            .map(|(&point, _)| point)
+            .collect::<Vec<_>>();
+
+        points.sort();
+        points
+    }
+
+    // Lowest strictly lower neighbor, or None if the cell is a sink
+    pub fn flow(&self, point: Point) -> Option<Point> {
+        let height = *self.map.get(&point)?;
+
+        self.neighbors(point)
+            .filter(|&(_, h)| h < height)
+            .min_by_key(|&(next, h)| (h, next))
+            .map(|(next, _)| next)
+    }
+
+    #[cfg(test)]
+    pub fn sink(&self, point: Point) -> Option<Point> {
+        self.map.get(&point)?;
+
+        let mut current = point;
+        while let Some(next) = self.flow(current) {
+            current = next;
+        }
+
+        Some(current)
+    }
+
+    // Cells grouped by the sink their steepest-descent path drains into
+    pub fn basins(&self) -> HashMap<Point, Vec<Point>> {
+        let mut sinks: HashMap<Point, Point> = HashMap::new();
+        let mut basins: HashMap<Point, Vec<Point>> = HashMap::new();
+
+        for &point in self.map.keys() {
+            let mut path = vec![point];
+            let mut current = point;
+
+            let sink = loop {
+                if let Some(&sink) = sinks.get(&current) {
+                    break sink;
+                }
+
+                match self.flow(current) {
+                    Some(next) => {
+                        path.push(next);
+                        current = next;
+                    }
+                    None => break current,
+                }
+            };
+
+            for point in path {
+                sinks.insert(point, sink);
+            }
+        }
+
+        for (point, sink) in sinks {
+            basins.entry(sink).or_default().push(point);
+        }
+
+        for points in basins.values_mut() {
+            points.sort();
+        }
+
+        basins
+    }
+
+    // Cells reachable from the start by moves that change the height by exactly `step`
+    pub fn reachable_area(&self, start: Point, step: i64) -> HashSet<Point> {
+        let mut visited = HashSet::new();
+        let mut stack = Vec::new();
+
+        if self.map.contains_key(&start) {
+            visited.insert(start);
+            stack.push(start);
+        }
+
+        while let Some(point) = stack.pop() {
+            let next_height = self.map[&point].checked_add_signed(step);
+
+            for (next, height) in self.neighbors(point) {
+                if Some(height) == next_height && visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        visited
+    }
+
+    pub fn reachable_areas(&self, start_height: u64, step: i64) -> HashMap<Point, HashSet<Point>> {
+        self.map
+            .iter()
+            .filter(|&(_, &height)| height == start_height)
+            .map(|(&point, _)| (point, self.reachable_area(point, step)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_input;
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test-input.txt");
+    const TEST_INPUT_TINY: &str = include_str!("test-input-tiny.txt");
+    const TEST_INPUT_IMPASSABLE: &str = include_str!("test-input-impassable.txt");
+
+    #[test]
+    fn test_extrema() {
+        let map = parse_input(TEST_INPUT_TINY).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        assert_eq!(heightmap.height_range(), Some((0, 9)));
+        assert_eq!(heightmap.local_minima(), vec![Point(0, 0)]);
+        assert_eq!(heightmap.local_maxima(), vec![Point(0, 3)]);
+
+        let map = parse_input(TEST_INPUT_IMPASSABLE).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        assert_eq!(heightmap.local_minima(), vec![Point(3, 0)]);
+        assert_eq!(heightmap.local_maxima(), vec![Point(0, 6), Point(6, 6)]);
+        assert_eq!(Heightmap::new(&HashMap::new()).height_range(), None);
+    }
+
+    #[test]
+    fn test_basins() {
+        let map = parse_input(TEST_INPUT_TINY).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        let basins = heightmap.basins();
+        assert_eq!(basins.len(), 1);
+        assert_eq!(basins[&Point(0, 0)].len(), 16);
+        assert_eq!(heightmap.flow(Point(1, 1)), Some(Point(0, 1)));
+        assert_eq!(heightmap.sink(Point(0, 3)), Some(Point(0, 0)));
+        assert_eq!(heightmap.sink(Point(9, 9)), None);
+
+        let map = parse_input(TEST_INPUT).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        let basins = heightmap.basins();
+        assert_eq!(basins.values().map(|b| b.len()).sum::<usize>(), 64);
+        assert!(basins.keys().all(|sink| heightmap.flow(*sink).is_none()));
+        assert!(basins
+            .iter()
+            .all(|(sink, points)| points.iter().all(|&p| heightmap.sink(p) == Some(*sink))));
+    }
+
+    #[test]
+    fn test_reachable_areas() {
+        let map = parse_input(TEST_INPUT_IMPASSABLE).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        let areas = heightmap.reachable_areas(0, 1);
+        assert_eq!(areas.len(), 1);
+        assert_eq!(areas[&Point(3, 0)].len(), 16);
+
+        let area = heightmap.reachable_area(Point(0, 6), -1);
+        assert_eq!(area.len(), 10);
+        assert!(!area.contains(&Point(6, 6)));
+
+        let map = parse_input(TEST_INPUT).unwrap();
+        let heightmap = Heightmap::new(&map);
+
+        assert_eq!(heightmap.reachable_areas(0, 1).len(), 9);
+        assert!(heightmap
+            .reachable_area(Point(0, 0), 1)
+            .contains(&Point(1, 0)));
+    }
+}
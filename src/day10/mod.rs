@@ -1,113 +1,189 @@
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    error::Error,
-};
+mod heightmap;
+pub mod trails;
+
+use std::{collections::HashMap, error::Error};
 
-use crate::{
-    util::{Direction, Point},
-    Puzzle,
+use heightmap::Heightmap;
+use nom::{
+    character::complete::{char, i64, u64},
+    combinator::all_consuming,
+    sequence::{preceded, tuple},
 };
+use trails::{TrailCounter, TrailRules};
+
+use crate::{util::Point, Puzzle};
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+#[derive(Default)]
+pub struct Part1 {
+    rules: TrailRules,
+}
 
 impl Part1 {
-    pub fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_rules(rules: TrailRules) -> Self {
+        Self { rules }
+    }
+
+    pub fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
         let map = parse_input(input)?;
 
-        Ok(find_trailheads(&map)
-            .iter()
-            .map(|&point| {
-                let trails = find_trails(&map, point);
-                let targets = trails
+        Ok(TrailCounter::new(&map, self.rules).total_score())
+    }
+
+    // Terrain summary, then one line per trailhead with the area it reaches and its peaks
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let map = parse_input(input)?;
+        let counter = TrailCounter::new(&map, self.rules);
+        let heightmap = Heightmap::new(&map);
+        let areas = heightmap.reachable_areas(self.rules.start(), self.rules.step());
+
+        let trailheads = counter
+            .trailheads()
+            .into_iter()
+            .map(|trailhead| {
+                let mut peaks = counter.peaks(trailhead).into_iter().collect::<Vec<_>>();
+                peaks.sort();
+
+                let peaks = peaks
                     .iter()
-                    .map(|trail| trail.back().unwrap())
-                    .collect::<HashSet<_>>();
-                targets.len() as u64
+                    .map(|peak| peak.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                format!(
+                    "{trailhead}: score {}, area {} [{peaks}]",
+                    counter.score(trailhead),
+                    areas.get(&trailhead).map_or(0, |area| area.len()),
+                )
             })
-            .sum())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(format!("{}\n{trailheads}", describe(&heightmap)))
     }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
     }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    rules: TrailRules,
+}
 
 impl Part2 {
-    pub fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_rules(rules: TrailRules) -> Self {
+        Self { rules }
+    }
+
+    pub fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
         let map = parse_input(input)?;
 
-        Ok(find_trailheads(&map)
-            .iter()
-            .map(|&point| {
-                let trails = find_trails(&map, point);
-                trails.len() as u64
+        Ok(TrailCounter::new(&map, self.rules).total_rating())
+    }
+
+    // One line per trailhead with its rating and the first trail found from it
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let map = parse_input(input)?;
+        let counter = TrailCounter::new(&map, self.rules);
+
+        Ok(counter
+            .trailheads()
+            .into_iter()
+            .map(|trailhead| {
+                let trail = counter
+                    .trails(trailhead)
+                    .next()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|point| point.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                format!(
+                    "{trailhead}: rating {} [{trail}]",
+                    counter.rating(trailhead)
+                )
             })
-            .sum())
+            .collect::<Vec<_>>()
+            .join("\n"))
     }
 }
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
     }
 }
 
+fn describe(heightmap: &Heightmap) -> String {
+    let heights = heightmap
+        .height_range()
+        .map_or("none".to_string(), |(min, max)| format!("{min}-{max}"));
+
+    let basins = heightmap.basins();
+    let largest = basins
+        .values()
+        .map(|points| points.len())
+        .max()
+        .unwrap_or(0);
+
+    format!(
+        "heights {heights}, {} peaks, {} pits, {} basins (largest {largest})",
+        heightmap.local_maxima().len(),
+        heightmap.local_minima().len(),
+        basins.len(),
+    )
+}
+
+pub fn parse_rules(input: &str) -> Result<TrailRules, String> {
+    // "0,9,1", as given on the command line
+    let (_, (start, end, step)) = all_consuming(tuple((
+        u64::<_, nom::error::Error<_>>,
+        preceded(char(','), u64),
+        preceded(char(','), i64),
+    )))(input)
+    .map_err(|_| format!("Invalid trail rules '{input}', expected START,END,STEP"))?;
+
+    TrailRules::new(start, end, step).map_err(|e| e.to_string())
+}
+
 fn parse_input(input: &str) -> Result<HashMap<Point, u64>, Box<dyn std::error::Error>> {
     Ok(input
         .lines()
         .enumerate()
         .flat_map(|(y, line)| {
-            line.chars().enumerate().map(move |(x, c)| {
-                c.to_digit(10)
-                    .ok_or_else(|| format!("Invalid digit: {}", c))
-                    .map(|height| (Point(x as i64, y as i64), height as u64))
-            })
+            // Impassable cells are left out of the map entirely
+            line.chars()
+                .enumerate()
+                .filter(|&(_, c)| c != '.')
+                .map(move |(x, c)| {
+                    c.to_digit(10)
+                        .ok_or_else(|| format!("Invalid digit: {}", c))
+                        .map(|height| (Point(x as i64, y as i64), height as u64))
+                })
         })
         .collect::<Result<_, _>>()?)
 }
 
-fn find_trailheads(map: &HashMap<Point, u64>) -> Vec<Point> {
-    map.iter()
-        .filter(|&(_, &height)| height == 0)
-        .map(|(&point, _)| point)
-        .collect::<Vec<_>>()
-}
-
-fn find_trails(map: &HashMap<Point, u64>, point: Point) -> Vec<VecDeque<Point>> {
-    let mut trails = Vec::new();
-
-    if let Some(&height) = map.get(&point) {
-        if height == 9 {
-            trails.push(VecDeque::from([point]));
-        } else {
-            for dir in Direction::all() {
-                let next_point = point + dir;
-                let next_height = map.get(&next_point);
-                if next_height == Some(&(height + 1)) {
-                    let next_trails = find_trails(map, next_point);
-                    for mut trail in next_trails {
-                        trail.push_front(point);
-                        trails.push(trail);
-                    }
-                }
-            }
-        }
-    }
-
-    trails
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const TEST_INPUT: &str = include_str!("test-input.txt");
+    const TEST_INPUT_IMPASSABLE: &str = include_str!("test-input-impassable.txt");
 
     #[test]
     fn test_parse_input() {
@@ -115,15 +191,46 @@ mod tests {
         assert_eq!(map.len(), 64);
         assert_eq!(map.get(&Point(0, 0)), Some(&8));
         assert_eq!(map.get(&Point(7, 7)), Some(&2));
+
+        let map = parse_input(TEST_INPUT_IMPASSABLE).unwrap();
+        assert_eq!(map.len(), 16);
+        assert_eq!(map.get(&Point(0, 0)), None);
+        assert!(parse_input("01x").is_err());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(
+            parse_rules("9,0,-1"),
+            Ok(TrailRules::new(9, 0, -1).unwrap())
+        );
+        assert!(parse_rules("0,9,0").is_err());
+        assert!(parse_rules("0,9").is_err());
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(Part1::solve_input(TEST_INPUT).unwrap(), 36);
+        let part = Part1::default();
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 36);
+        assert_eq!(part.solve_input(TEST_INPUT_IMPASSABLE).unwrap(), 2);
+
+        let report = part.report_input(TEST_INPUT).unwrap();
+        assert_eq!(report.lines().count(), 10);
+        assert!(report.starts_with("heights 0-9, "));
+        assert!(report.contains("\n0,6: score 5, area "));
+
+        let part = Part1::with_rules(TrailRules::new(9, 0, -1).unwrap());
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 36);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 81);
+        let part = Part2::default();
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 81);
+        assert_eq!(part.solve_input(TEST_INPUT_IMPASSABLE).unwrap(), 2);
+
+        let report = part.report_input(TEST_INPUT).unwrap();
+        assert_eq!(report.lines().count(), 9);
+        assert!(report.starts_with("0,6: rating 5 [0,6 "));
     }
 }
@@ -0,0 +1,280 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use crate::util::{Direction, Point};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TrailRules {
+    start: u64,
+    end: u64,
+    step: i64,
+}
+
+impl TrailRules {
+    pub fn new(start: u64, end: u64, step: i64) -> Result<Self, Box<dyn Error>> {
+        if step == 0 {
+            return Err("Step must be non-zero".into());
+        }
+
+        Ok(Self { start, end, step })
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn step(&self) -> i64 {
+        self.step
+    }
+
+    // Heights along a trail, from the end back to the start
+    fn levels(&self) -> Vec<u64> {
+        let diff = self.end as i64 - self.start as i64;
+
+        if diff % self.step != 0 || diff / self.step < 0 {
+            return Vec::new();
+        }
+
+        (0..=diff / self.step)
+            .map(|k| (self.end as i64 - k * self.step) as u64)
+            .collect()
+    }
+}
+
+impl Default for TrailRules {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: 9,
+            step: 1,
+        }
+    }
+}
+
+pub struct TrailCounter<'a> {
+    map: &'a HashMap<Point, u64>,
+    rules: TrailRules,
+    ratings: HashMap<Point, u64>,
+    peaks: Vec<Point>,
+    // Bitset over `peaks` of the ones each trailhead reaches
+    reachable: HashMap<Point, Vec<u64>>,
+}
+
+impl<'a> TrailCounter<'a> {
+    pub fn new(map: &'a HashMap<Point, u64>, rules: TrailRules) -> Self {
+        let mut by_height: HashMap<u64, Vec<Point>> = HashMap::new();
+
+        for (&point, &height) in map {
+            by_height.entry(height).or_default().push(point);
+        }
+
+        let mut peaks = by_height.get(&rules.end).cloned().unwrap_or_default();
+        peaks.sort();
+
+        let words = peaks.len().div_ceil(64);
+        let mut ratings = HashMap::new();
+        let mut reachable: HashMap<Point, Vec<u64>> = HashMap::new();
+
+        // One pass from the peaks down, each point taking the union of the peak sets of the
+        // points it leads to; only the level above is kept around
+        for height in rules.levels() {
+            let mut level = HashMap::new();
+
+            for &point in by_height.get(&height).into_iter().flatten() {
+                let (rating, bits) = if height == rules.end {
+                    let mut bits = vec![0; words];
+                    let i = peaks.binary_search(&point).unwrap();
+                    bits[i / 64] |= 1 << (i % 64);
+
+                    (1, bits)
+                } else {
+                    next_points(map, rules.step, point)
+                        .filter_map(|next| Some((ratings.get(&next)?, reachable.get(&next)?)))
+                        .fold((0, vec![0; words]), |(rating, mut bits), (r, next_bits)| {
+                            bits.iter_mut().zip(next_bits).for_each(|(a, b)| *a |= b);
+                            (rating + r, bits)
+                        })
+                };
+
+                if rating > 0 {
+                    ratings.insert(point, rating);
+                    level.insert(point, bits);
+                }
+            }
+
+            reachable = level;
+        }
+
+        Self {
+            map,
+            rules,
+            ratings,
+            peaks,
+            reachable,
+        }
+    }
+
+    pub fn trailheads(&self) -> Vec<Point> {
+        let mut trailheads = self
+            .map
+            .iter()
+            .filter(|&(_, &height)| height == self.rules.start)
+            .map(|(&point, _)| point)
+            .collect::<Vec<_>>();
+
+        trailheads.sort();
+        trailheads
+    }
+
+    pub fn peaks(&self, trailhead: Point) -> HashSet<Point> {
+        let Some(bits) = self.reachable.get(&trailhead) else {
+            return HashSet::new();
+        };
+
+        self.peaks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bits[i / 64] & (1 << (i % 64)) != 0)
+            .map(|(_, &peak)| peak)
+            .collect()
+    }
+
+    pub fn score(&self, trailhead: Point) -> u64 {
+        self.reachable.get(&trailhead).map_or(0, |bits| {
+            bits.iter().map(|word| word.count_ones() as u64).sum()
+        })
+    }
+
+    pub fn rating(&self, trailhead: Point) -> u64 {
+        self.ratings.get(&trailhead).copied().unwrap_or(0)
+    }
+
+    pub fn total_score(&self) -> u64 {
+        self.trailheads().iter().map(|&p| self.score(p)).sum()
+    }
+
+    pub fn total_rating(&self) -> u64 {
+        self.trailheads().iter().map(|&p| self.rating(p)).sum()
+    }
+
+    // Lazily walks every trail, only descending into points that lead to a peak
+    pub fn trails(&self, trailhead: Point) -> impl Iterator<Item = Vec<Point>> + '_ {
+        let (map, rules, ratings) = (self.map as &HashMap<_, _>, self.rules, &self.ratings);
+
+        let mut path = Vec::new();
+        let mut stack = vec![if self.rating(trailhead) > 0
+            && self.map[&trailhead] == self.rules.start
+        {
+            vec![trailhead]
+        } else {
+            Vec::new()
+        }];
+
+        std::iter::from_fn(move || loop {
+            let candidates = stack.last_mut()?;
+
+            match candidates.pop() {
+                Some(point) => {
+                    path.push(point);
+
+                    if map[&point] == rules.end {
+                        let trail = path.clone();
+                        path.pop();
+                        return Some(trail);
+                    }
+
+                    stack.push(
+                        next_points(map, rules.step, point)
+                            .filter(|next| ratings.contains_key(next))
+                            .collect(),
+                    );
+                }
+                None => {
+                    stack.pop();
+                    path.pop();
+                }
+            }
+        })
+    }
+}
+
+fn next_points(
+    map: &HashMap<Point, u64>,
+    step: i64,
+    point: Point,
+) -> impl Iterator<Item = Point> + '_ {
+    let next_height = map[&point].checked_add_signed(step);
+
+    Direction::all()
+        .map(move |dir| point + dir)
+        .filter(move |next| next_height.is_some() && map.get(next).copied() == next_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_input;
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test-input.txt");
+    const TEST_INPUT_TINY: &str = include_str!("test-input-tiny.txt");
+
+    #[test]
+    fn test_counter() {
+        let map = parse_input(TEST_INPUT).unwrap();
+        let counter = TrailCounter::new(&map, TrailRules::default());
+
+        assert_eq!(counter.trailheads().len(), 9);
+        assert_eq!(counter.total_score(), 36);
+        assert_eq!(counter.total_rating(), 81);
+        assert_eq!(counter.score(Point(2, 0)), 5);
+        assert_eq!(counter.rating(Point(2, 0)), 20);
+        assert_eq!(counter.rating(Point(100, 100)), 0);
+    }
+
+    #[test]
+    fn test_trails() {
+        let map = parse_input(TEST_INPUT).unwrap();
+        let counter = TrailCounter::new(&map, TrailRules::default());
+
+        for trailhead in counter.trailheads() {
+            let trails = counter.trails(trailhead).collect::<Vec<_>>();
+
+            assert_eq!(trails.len() as u64, counter.rating(trailhead));
+            assert_eq!(
+                trails
+                    .iter()
+                    .map(|trail| *trail.last().unwrap())
+                    .collect::<HashSet<_>>(),
+                counter.peaks(trailhead)
+            );
+            assert!(trails.iter().all(|trail| trail.len() == 10));
+        }
+
+        let map = parse_input(TEST_INPUT_TINY).unwrap();
+        let counter = TrailCounter::new(&map, TrailRules::default());
+        let trails = counter.trails(Point(0, 0)).collect::<Vec<_>>();
+
+        assert_eq!(trails.len(), 16);
+        assert!(trails.iter().all(|trail| trail[9] == Point(0, 3)));
+    }
+
+    #[test]
+    fn test_generalized_rules() {
+        let map = parse_input(TEST_INPUT_TINY).unwrap();
+
+        let descending = TrailCounter::new(&map, TrailRules::new(9, 0, -1).unwrap());
+        assert_eq!(descending.trailheads(), vec![Point(0, 3)]);
+        assert_eq!(descending.total_rating(), 16);
+
+        let short = TrailCounter::new(&map, TrailRules::new(0, 3, 1).unwrap());
+        assert_eq!(short.total_score(), 2);
+        assert_eq!(short.total_rating(), 4);
+
+        let skipping = TrailCounter::new(&map, TrailRules::new(0, 8, 2).unwrap());
+        assert_eq!(skipping.total_rating(), 0);
+
+        assert!(TrailRules::new(0, 9, 0).is_err());
+    }
+}
@@ -1,16 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
-use super::stone::Stone;
+use super::{graph::TransitionGraph, rules::RuleSet, stone::Stone};
 
 pub struct Game {
     cache: HashMap<(Stone, u64), u64>,
+    transform_cache: HashMap<Stone, Vec<Stone>>,
+    rules: RuleSet,
+}
+
+// When the set of every value seen so far stops growing. `seen_count` counts values that
+// appeared in any round, so it can exceed the `distinct_count` of every single round
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Saturation {
+    pub round: u64,
+    pub seen_count: usize,
 }
 
 impl Game {
     pub fn new() -> Self {
+        Self::with_rules(RuleSet::default())
+    }
+
+    pub fn with_rules(rules: RuleSet) -> Self {
         Self {
             cache: HashMap::new(),
+            transform_cache: HashMap::new(),
+            rules,
         }
     }
 
@@ -36,17 +52,125 @@ impl Game {
             return Ok(res);
         }
 
-        let stones = stone.transform()?;
+        let stones = self.transform(stone)?;
         let res = self.evolve_stones(&stones, round_count - 1)?;
 
         self.cache.insert(key, res);
 
         Ok(res)
     }
+
+    // Exact count through the transition graph, cheap for round counts far past what the
+    // recursive cache can hold
+    pub fn count_stones(&self, stones: &[Stone], round_count: u64) -> Result<u128, Box<dyn Error>> {
+        TransitionGraph::build(stones, &self.rules)?.count_stones(stones, round_count)
+    }
+
+    pub fn count_stones_mod(
+        &self,
+        stones: &[Stone],
+        round_count: u64,
+        modulus: u64,
+    ) -> Result<u64, Box<dyn Error>> {
+        TransitionGraph::build(stones, &self.rules)?.count_stones_mod(stones, round_count, modulus)
+    }
+
+    pub fn histogram(
+        &mut self,
+        stones: &[Stone],
+        round_count: u64,
+    ) -> Result<HashMap<Stone, u64>, Box<dyn Error>> {
+        let mut histogram = HashMap::new();
+
+        for &stone in stones {
+            *histogram.entry(stone).or_insert(0) += 1;
+        }
+
+        for _ in 0..round_count {
+            let mut next: HashMap<Stone, u64> = HashMap::new();
+
+            for (stone, count) in histogram {
+                for new_stone in self.transform(stone)? {
+                    let entry = next.entry(new_stone).or_insert(0);
+                    *entry = entry.checked_add(count).ok_or("Overflow")?;
+                }
+            }
+
+            histogram = next;
+        }
+
+        Ok(histogram)
+    }
+
+    // Number of different values on the line after exactly `round_count` blinks
+    pub fn distinct_count(
+        &mut self,
+        stones: &[Stone],
+        round_count: u64,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut current = stones.iter().copied().collect::<HashSet<_>>();
+
+        for _ in 0..round_count {
+            let mut next = HashSet::new();
+
+            for stone in current {
+                next.extend(self.transform(stone)?);
+            }
+
+            current = next;
+        }
+
+        Ok(current.len())
+    }
+
+    // Rule sets like `* -> n + 1` keep producing new values, so give up after `max_rounds`
+    pub fn saturation(
+        &mut self,
+        stones: &[Stone],
+        max_rounds: u64,
+    ) -> Result<Saturation, Box<dyn Error>> {
+        let mut seen = stones.iter().copied().collect::<HashSet<_>>();
+        let mut frontier = seen.iter().copied().collect::<Vec<_>>();
+
+        for round in 0..=max_rounds {
+            let mut next = Vec::new();
+
+            for stone in frontier {
+                for new_stone in self.transform(stone)? {
+                    if seen.insert(new_stone) {
+                        next.push(new_stone);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                return Ok(Saturation {
+                    round,
+                    seen_count: seen.len(),
+                });
+            }
+
+            frontier = next;
+        }
+
+        Err(format!("New stone values still appear after {max_rounds} rounds").into())
+    }
+
+    fn transform(&mut self, stone: Stone) -> Result<Vec<Stone>, Box<dyn Error>> {
+        if let Some(stones) = self.transform_cache.get(&stone) {
+            return Ok(stones.clone());
+        }
+
+        let stones = stone.transform(&self.rules)?;
+        self.transform_cache.insert(stone, stones.clone());
+
+        Ok(stones)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::parse::parse_rules;
     use super::*;
 
     #[test]
@@ -71,4 +195,69 @@ mod tests {
             65601038650482
         );
     }
+
+    #[test]
+    fn test_histogram() {
+        let mut game = Game::new();
+        let stones = [Stone::new(125), Stone::new(17)];
+
+        let histogram = game.histogram(&stones, 6).unwrap();
+
+        assert_eq!(histogram.values().sum::<u64>(), 22);
+        assert_eq!(histogram[&Stone::new(2)], 4);
+        assert_eq!(histogram[&Stone::new(2097446912)], 1);
+        assert_eq!(game.distinct_count(&stones, 6).unwrap(), histogram.len());
+
+        let histogram = game.histogram(&stones, 75).unwrap();
+        assert_eq!(
+            histogram.values().sum::<u64>(),
+            game.evolve_stones(&stones, 75).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut game = Game::new();
+
+        let saturation = game.saturation(&[Stone::new(0)], 100).unwrap();
+        assert_eq!(saturation.seen_count, 54);
+        assert_eq!(saturation.round, 16);
+        assert!(game.saturation(&[Stone::new(0)], 15).is_err());
+        assert!(game.distinct_count(&[Stone::new(0)], 16).unwrap() < 54);
+
+        let (_, rules) = parse_rules("* -> 1").unwrap();
+        let mut game = Game::with_rules(rules);
+        assert_eq!(
+            game.saturation(&[Stone::new(5)], 100).unwrap(),
+            Saturation {
+                round: 1,
+                seen_count: 2
+            }
+        );
+        assert_eq!(game.distinct_count(&[Stone::new(5)], 1).unwrap(), 1);
+
+        let (_, rules) = parse_rules("* -> n + 1").unwrap();
+        let mut game = Game::with_rules(rules);
+        assert!(game.saturation(&[Stone::new(0)], 1_000).is_err());
+    }
+
+    #[test]
+    fn test_count_stones_mod() {
+        let game = Game::new();
+        let stones = [Stone::new(125), Stone::new(17)];
+
+        assert_eq!(game.count_stones(&stones, 75).unwrap(), 65601038650482);
+        assert_eq!(
+            game.count_stones_mod(&stones, 75, 1_000_000_000).unwrap(),
+            601038650482 % 1_000_000_000
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let (_, rules) = parse_rules("n == 0 -> 1\n* -> n + 1, n").unwrap();
+        let mut game = Game::with_rules(rules);
+
+        assert_eq!(game.evolve_stones(&[Stone::new(0)], 10).unwrap(), 512);
+    }
 }
@@ -0,0 +1,578 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::error::Error;
+
+use super::{rules::RuleSet, stone::Stone};
+
+// Primes the integer recurrence of the counts is found over; the first two fix its
+// coefficients and the third checks them
+const PRIMES: [u64; 3] = [
+    4_611_686_018_427_387_847,
+    4_611_686_018_427_387_817,
+    4_611_686_018_427_387_787,
+];
+
+pub struct TransitionGraph {
+    index: HashMap<Stone, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl TransitionGraph {
+    pub fn build(stones: &[Stone], rules: &RuleSet) -> Result<Self, Box<dyn Error>> {
+        let mut index = HashMap::new();
+        let mut queue = Vec::new();
+
+        for &stone in stones {
+            if let Entry::Vacant(entry) = index.entry(stone) {
+                entry.insert(queue.len());
+                queue.push(stone);
+            }
+        }
+
+        let mut edges = Vec::new();
+
+        while let Some(&stone) = queue.get(edges.len()) {
+            let targets = stone
+                .transform(rules)?
+                .into_iter()
+                .map(|next| {
+                    *index.entry(next).or_insert_with(|| {
+                        queue.push(next);
+                        queue.len() - 1
+                    })
+                })
+                .collect();
+
+            edges.push(targets);
+        }
+
+        Ok(Self { index, edges })
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    // Exact count, or an error once it no longer fits
+    pub fn count_stones(&self, stones: &[Stone], round_count: u64) -> Result<u128, Box<dyn Error>> {
+        let count = if round_count <= self.len() as u64 {
+            *self
+                .totals(&Exact, stones, round_count as usize + 1)?
+                .last()
+                .unwrap()
+        } else {
+            self.count_with(&Exact, stones, round_count)?
+        };
+
+        Ok(count.try_into()?)
+    }
+
+    pub fn count_stones_mod(
+        &self,
+        stones: &[Stone],
+        round_count: u64,
+        modulus: u64,
+    ) -> Result<u64, Box<dyn Error>> {
+        if modulus == 0 {
+            return Err("Modulus must be positive".into());
+        }
+
+        self.count_with(&Modulo(modulus), stones, round_count)
+    }
+
+    // The count after n rounds is 1^T M^n v for the transition matrix M. Its powers are
+    // taken through the companion matrix of the shortest recurrence of the counts, i.e.
+    // as x^n modulo that polynomial, which costs O(order^2 log n) instead of O(len^3 log n)
+    fn count_with<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+        stones: &[Stone],
+        round_count: u64,
+    ) -> Result<A::Value, Box<dyn Error>> {
+        let recurrence = self
+            .recurrence(stones)?
+            .into_iter()
+            .map(|c| arithmetic.integer(c))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let terms = self.totals(arithmetic, stones, recurrence.len().max(1))?;
+
+        if let Some(&term) = terms.get(round_count as usize) {
+            return Ok(term);
+        }
+
+        nth_term(arithmetic, &terms, &recurrence, round_count)
+    }
+
+    // Integer coefficients c with total[i] = sum(c[j] * total[i - 1 - j]). They are found
+    // with Berlekamp-Massey over each prime and put back together with the CRT
+    fn recurrence(&self, stones: &[Stone]) -> Result<Vec<i128>, Box<dyn Error>> {
+        let term_count = 2 * self.len() + 2;
+
+        let mut recurrences = Vec::new();
+
+        for p in PRIMES {
+            let terms = self.totals(&Modulo(p), stones, term_count)?;
+            recurrences.push(berlekamp_massey(&terms, p));
+        }
+
+        let [first, second, check] = &recurrences[..] else {
+            unreachable!();
+        };
+
+        let (p, q) = (PRIMES[0] as i128, PRIMES[1] as i128);
+        let p_inverse = pow_mod(PRIMES[0] % PRIMES[1], PRIMES[1] - 2, PRIMES[1]) as i128;
+
+        let coefficients = first
+            .iter()
+            .zip(second)
+            .map(|(&a, &b)| {
+                let (a, b) = (a as i128, b as i128);
+                let x = a + p * ((b - a).rem_euclid(q) * p_inverse % q);
+
+                if x > p * q / 2 {
+                    x - p * q
+                } else {
+                    x
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let matches_check = coefficients
+            .iter()
+            .zip(check)
+            .all(|(&c, &r)| c.rem_euclid(PRIMES[2] as i128) == r as i128);
+
+        if first.len() != second.len() || first.len() != check.len() || !matches_check {
+            return Err("Stone counts follow no recurrence with small enough coefficients".into());
+        }
+
+        Ok(coefficients)
+    }
+
+    // Total stone count for each of the first `term_count` rounds
+    fn totals<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+        stones: &[Stone],
+        term_count: usize,
+    ) -> Result<Vec<A::Value>, Box<dyn Error>> {
+        let zero = arithmetic.integer(0)?;
+        let mut counts = vec![zero; self.len()];
+
+        for stone in stones {
+            let &i = self
+                .index
+                .get(stone)
+                .ok_or_else(|| format!("Stone {stone} is not in the graph"))?;
+
+            counts[i] = arithmetic.add(counts[i], arithmetic.integer(1)?)?;
+        }
+
+        let mut totals = Vec::new();
+
+        for _ in 0..term_count {
+            totals.push(
+                counts
+                    .iter()
+                    .try_fold(zero, |acc, &c| arithmetic.add(acc, c))?,
+            );
+
+            let mut next = vec![zero; counts.len()];
+
+            for (i, &count) in counts.iter().enumerate().filter(|(_, &c)| c != zero) {
+                for &j in &self.edges[i] {
+                    next[j] = arithmetic.add(next[j], count)?;
+                }
+            }
+
+            counts = next;
+        }
+
+        Ok(totals)
+    }
+}
+
+// Numbers the counts are taken in. Products are summed as `Sum` and only reduced back to
+// a `Value` once per coefficient, which keeps the modular polynomial products cheap
+trait Arithmetic {
+    type Value: Copy + PartialEq;
+    type Sum: Copy;
+
+    fn integer(&self, n: i128) -> Result<Self::Value, Box<dyn Error>>;
+    fn add(&self, a: Self::Value, b: Self::Value) -> Result<Self::Value, Box<dyn Error>>;
+    fn empty(&self) -> Self::Sum;
+    fn add_product(
+        &self,
+        sum: Self::Sum,
+        a: Self::Value,
+        b: Self::Value,
+    ) -> Result<Self::Sum, Box<dyn Error>>;
+    fn reduce(&self, sum: Self::Sum) -> Self::Value;
+}
+
+struct Modulo(u64);
+
+impl Arithmetic for Modulo {
+    type Value = u64;
+    type Sum = u128;
+
+    fn integer(&self, n: i128) -> Result<u64, Box<dyn Error>> {
+        Ok(n.rem_euclid(self.0 as i128) as u64)
+    }
+
+    fn add(&self, a: u64, b: u64) -> Result<u64, Box<dyn Error>> {
+        Ok(add_mod(a, b, self.0))
+    }
+
+    fn empty(&self) -> u128 {
+        0
+    }
+
+    fn add_product(&self, sum: u128, a: u64, b: u64) -> Result<u128, Box<dyn Error>> {
+        // Below 2^32 the raw products are small enough to add up unreduced
+        let product = if self.0 <= u32::MAX as u64 {
+            a as u128 * b as u128
+        } else {
+            mul_mod(a, b, self.0) as u128
+        };
+
+        Ok(sum + product)
+    }
+
+    fn reduce(&self, sum: u128) -> u64 {
+        (sum % self.0 as u128) as u64
+    }
+}
+
+struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = i128;
+    type Sum = i128;
+
+    fn integer(&self, n: i128) -> Result<i128, Box<dyn Error>> {
+        Ok(n)
+    }
+
+    fn add(&self, a: i128, b: i128) -> Result<i128, Box<dyn Error>> {
+        a.checked_add(b).ok_or_else(|| "Overflow".into())
+    }
+
+    fn empty(&self) -> i128 {
+        0
+    }
+
+    fn add_product(&self, sum: i128, a: i128, b: i128) -> Result<i128, Box<dyn Error>> {
+        a.checked_mul(b)
+            .and_then(|product| sum.checked_add(product))
+            .ok_or_else(|| "Overflow".into())
+    }
+
+    fn reduce(&self, sum: i128) -> i128 {
+        sum
+    }
+}
+
+fn add_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    let (sum, overflow) = a.overflowing_add(b);
+
+    if overflow || sum >= modulus {
+        sum.wrapping_sub(modulus)
+    } else {
+        sum
+    }
+}
+
+fn sub_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    add_mod(a, modulus - b % modulus, modulus)
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+// Shortest recurrence terms[i] = sum(c[j] * terms[i - 1 - j]) over a prime field
+fn berlekamp_massey(terms: &[u64], modulus: u64) -> Vec<u64> {
+    let mut current = vec![1];
+    let mut previous = vec![1];
+    let mut len = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1;
+
+    for i in 0..terms.len() {
+        let discrepancy = (1..=len).fold(terms[i], |acc, j| {
+            add_mod(acc, mul_mod(current[j], terms[i - j], modulus), modulus)
+        });
+
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let coef = mul_mod(
+            discrepancy,
+            pow_mod(previous_discrepancy, modulus - 2, modulus),
+            modulus,
+        );
+
+        let snapshot = current.clone();
+
+        if current.len() < previous.len() + shift {
+            current.resize(previous.len() + shift, 0);
+        }
+
+        for (j, &p) in previous.iter().enumerate() {
+            current[j + shift] = sub_mod(current[j + shift], mul_mod(coef, p, modulus), modulus);
+        }
+
+        if 2 * len <= i {
+            len = i + 1 - len;
+            previous = snapshot;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+
+    current.resize(len + 1, 0);
+
+    current[1..]
+        .iter()
+        .map(|&c| sub_mod(0, c, modulus))
+        .collect()
+}
+
+// Computes terms[n] as x^n reduced modulo the characteristic polynomial
+fn nth_term<A: Arithmetic>(
+    arithmetic: &A,
+    terms: &[A::Value],
+    recurrence: &[A::Value],
+    n: u64,
+) -> Result<A::Value, Box<dyn Error>> {
+    let order = recurrence.len();
+    let zero = arithmetic.integer(0)?;
+
+    if order == 0 {
+        return Ok(zero);
+    }
+
+    let mul = |a: &[A::Value], b: &[A::Value]| -> Result<Vec<A::Value>, Box<dyn Error>> {
+        let mut product = vec![arithmetic.empty(); 2 * order - 1];
+
+        for (i, &x) in a.iter().enumerate().filter(|(_, &x)| x != zero) {
+            for (j, &y) in b.iter().enumerate() {
+                product[i + j] = arithmetic.add_product(product[i + j], x, y)?;
+            }
+        }
+
+        for i in (order..product.len()).rev() {
+            let top = arithmetic.reduce(product[i]);
+
+            if top == zero {
+                continue;
+            }
+
+            for (j, &c) in recurrence.iter().enumerate() {
+                let k = i - 1 - j;
+                product[k] = arithmetic.add_product(product[k], top, c)?;
+            }
+        }
+
+        Ok(product[..order]
+            .iter()
+            .map(|&sum| arithmetic.reduce(sum))
+            .collect())
+    };
+
+    let mut result = vec![zero; order];
+    result[0] = arithmetic.integer(1)?;
+
+    let mut base = vec![zero; order];
+    if order == 1 {
+        base[0] = recurrence[0];
+    } else {
+        base[1] = arithmetic.integer(1)?;
+    }
+
+    let mut exponent = n;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul(&result, &base)?;
+        }
+
+        exponent >>= 1;
+
+        if exponent > 0 {
+            base = mul(&base, &base)?;
+        }
+    }
+
+    result
+        .iter()
+        .zip(terms)
+        .try_fold(arithmetic.empty(), |acc, (&r, &t)| {
+            arithmetic.add_product(acc, r, t)
+        })
+        .map(|sum| arithmetic.reduce(sum))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse::parse_rules;
+    use super::*;
+    use crate::day11::game::Game;
+
+    const MODULI: [u64; 4] = [1_000_000_007, 1_000_000_000, 1 << 40, u64::MAX];
+
+    #[test]
+    fn test_build() {
+        let graph = TransitionGraph::build(&[Stone::new(0)], &RuleSet::default()).unwrap();
+        assert_eq!(graph.len(), 54);
+    }
+
+    #[test]
+    fn test_count_stones() {
+        let stones = [Stone::new(125), Stone::new(17)];
+        let graph = TransitionGraph::build(&stones, &RuleSet::default()).unwrap();
+
+        assert_eq!(graph.count_stones(&stones, 25).unwrap(), 55312);
+        assert_eq!(graph.count_stones(&stones, 75).unwrap(), 65601038650482);
+        assert!(graph.count_stones(&stones, 1_000).is_err());
+        assert!(graph.count_stones(&[Stone::new(99_999)], 1).is_err());
+
+        // Past the end of the graph the count comes from the recurrence
+        let (_, rules) = parse_rules("n < 3 -> n + 1\n* -> 0, n").unwrap();
+        let graph = TransitionGraph::build(&[Stone::new(0)], &rules).unwrap();
+        let mut game = Game::with_rules(rules);
+
+        assert_eq!(
+            graph.count_stones(&[Stone::new(0)], 100).unwrap(),
+            game.evolve_stones(&[Stone::new(0)], 100).unwrap() as u128
+        );
+    }
+
+    #[test]
+    fn test_count_stones_mod() {
+        let stones = [Stone::new(0)];
+        let graph = TransitionGraph::build(&stones, &RuleSet::default()).unwrap();
+        let mut game = Game::new();
+
+        for round_count in [0, 1, 10, 75, 100] {
+            assert_eq!(
+                graph.count_stones(&stones, round_count).unwrap(),
+                game.evolve_stones(&stones, round_count).unwrap() as u128
+            );
+        }
+
+        for round_count in [0, 1, 10, 75, 150, 200] {
+            let exact = graph.count_stones(&stones, round_count).unwrap();
+
+            for modulus in MODULI {
+                assert_eq!(
+                    graph
+                        .count_stones_mod(&stones, round_count, modulus)
+                        .unwrap() as u128,
+                    exact % modulus as u128
+                );
+            }
+        }
+
+        assert_eq!(graph.count_stones_mod(&stones, 75, 1).unwrap(), 0);
+        assert!(graph.count_stones_mod(&stones, 75, 0).is_err());
+    }
+
+    // Counts modulo `modulus` from a plain histogram of stone values, without the graph
+    fn histogram_count(stones: &[Stone], rules: &RuleSet, round_count: u64, modulus: u64) -> u64 {
+        let mut histogram = HashMap::new();
+
+        for &stone in stones {
+            *histogram.entry(stone).or_insert(0) += 1;
+        }
+
+        for _ in 0..round_count {
+            let mut next = HashMap::new();
+
+            for (stone, count) in histogram {
+                for new_stone in stone.transform(rules).unwrap() {
+                    let entry = next.entry(new_stone).or_insert(0);
+                    *entry = add_mod(*entry, count, modulus);
+                }
+            }
+
+            histogram = next;
+        }
+
+        histogram
+            .values()
+            .fold(0, |acc, &count| add_mod(acc, count, modulus))
+    }
+
+    #[test]
+    fn test_recurrence_matches_histogram() {
+        let rule_sets = [
+            RuleSet::default(),
+            parse_rules("n < 3 -> n + 1\n* -> 0, n").unwrap().1,
+            parse_rules("n == 0 -> 1\ndigits % 2 == 0 -> split\n* -> n * 3")
+                .unwrap()
+                .1,
+        ];
+
+        for rules in rule_sets {
+            for stones in [vec![Stone::new(0)], vec![Stone::new(125), Stone::new(17)]] {
+                let graph = TransitionGraph::build(&stones, &rules).unwrap();
+                let order = graph.recurrence(&stones).unwrap().len() as u64;
+
+                // Well past the order, where every term comes from the recovered recurrence
+                for round_count in [2 * order + 1, 5 * order + 13, 1_500] {
+                    for modulus in MODULI {
+                        assert_eq!(
+                            graph
+                                .count_stones_mod(&stones, round_count, modulus)
+                                .unwrap(),
+                            histogram_count(&stones, &rules, round_count, modulus)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_stones_mod_large() {
+        let stones = [Stone::new(125), Stone::new(17)];
+        let graph = TransitionGraph::build(&stones, &RuleSet::default()).unwrap();
+
+        for modulus in MODULI {
+            let expected = *graph
+                .totals(&Modulo(modulus), &stones, 5_001)
+                .unwrap()
+                .last()
+                .unwrap();
+
+            assert_eq!(
+                graph.count_stones_mod(&stones, 5_000, modulus).unwrap(),
+                expected
+            );
+        }
+
+        assert!(graph
+            .count_stones_mod(&stones, 1_000_000_000_000_000_000, 1_000_000_000)
+            .is_ok());
+    }
+}
@@ -1,47 +1,141 @@
 mod game;
-mod parse;
+mod graph;
+pub mod parse;
+pub mod rules;
 mod stone;
 
 use std::error::Error;
 
 use game::Game;
 use parse::parse_input;
+use rules::RuleSet;
 
 use crate::Puzzle;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+// Rounds allowed for the set of seen values to stop growing in the report
+const MAX_SATURATION_ROUNDS: u64 = 1_000;
+
+// Most common values listed in the report
+const REPORT_VALUES: usize = 3;
+
+// Distinct values, most common values and saturation after the given number of blinks
+fn report_input(
+    input: &str,
+    rules: Option<&RuleSet>,
+    round_count: u64,
+) -> Result<String, Box<dyn Error>> {
+    let (_, stones) = parse_input(input).map_err(|e| e.to_owned())?;
+
+    let mut game = rules.map_or_else(Game::new, |rules| Game::with_rules(rules.clone()));
+
+    let mut histogram = game
+        .histogram(&stones, round_count)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    histogram.sort_by_key(|&(stone, count)| (std::cmp::Reverse(count), stone));
+
+    let common = histogram
+        .iter()
+        .take(REPORT_VALUES)
+        .map(|(stone, count)| format!("{stone} x{count}"))
+        .collect::<Vec<_>>();
+
+    let saturation = game.saturation(&stones, MAX_SATURATION_ROUNDS)?;
+
+    Ok(format!(
+        "{} distinct values, most common {}\nno new values after blink {} ({} values seen)",
+        game.distinct_count(&stones, round_count)?,
+        common.join(", "),
+        saturation.round,
+        saturation.seen_count,
+    ))
+}
+
+// Stone count after the given number of blinks; an explicit blink count or modulus goes
+// through the transition graph instead of the recursive cache
+fn count_input(
+    input: &str,
+    rules: Option<&RuleSet>,
+    default_blinks: u64,
+    blinks: Option<u64>,
+    modulus: Option<u64>,
+) -> Result<String, Box<dyn Error>> {
+    let (_, stones) = parse_input(input).map_err(|e| e.to_owned())?;
+
+    let mut game = rules.map_or_else(Game::new, |rules| Game::with_rules(rules.clone()));
+
+    Ok(match (blinks, modulus) {
+        (None, None) => game.evolve_stones(&stones, default_blinks)?.to_string(),
+        (blinks, None) => game
+            .count_stones(&stones, blinks.unwrap_or(default_blinks))?
+            .to_string(),
+        (blinks, Some(modulus)) => game
+            .count_stones_mod(&stones, blinks.unwrap_or(default_blinks), modulus)?
+            .to_string(),
+    })
+}
+
+#[derive(Default)]
+pub struct Part1 {
+    rules: Option<RuleSet>,
+    blinks: Option<u64>,
+    modulus: Option<u64>,
+}
 
 impl Part1 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, stones) = parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_blinks(rules: Option<RuleSet>, blinks: Option<u64>, modulus: Option<u64>) -> Self {
+        Self {
+            rules,
+            blinks,
+            modulus,
+        }
+    }
 
-        let mut game = Game::new();
-        game.evolve_stones(&stones, 25)
+    fn solve_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        count_input(input, self.rules.as_ref(), 25, self.blinks, self.modulus)
     }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT)
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        report_input(INPUT, self.rules.as_ref(), self.blinks.unwrap_or(25)).map(Some)
     }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    rules: Option<RuleSet>,
+    blinks: Option<u64>,
+    modulus: Option<u64>,
+}
 
 impl Part2 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, stones) = parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_blinks(rules: Option<RuleSet>, blinks: Option<u64>, modulus: Option<u64>) -> Self {
+        Self {
+            rules,
+            blinks,
+            modulus,
+        }
+    }
 
-        let mut game = Game::new();
-        game.evolve_stones(&stones, 75)
+    fn solve_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        count_input(input, self.rules.as_ref(), 75, self.blinks, self.modulus)
     }
 }
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT)
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        report_input(INPUT, self.rules.as_ref(), self.blinks.unwrap_or(75)).map(Some)
     }
 }
 
@@ -51,11 +145,35 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(Part1::solve_input("125 17").unwrap(), 55312);
+        assert_eq!(Part1::default().solve_input("125 17").unwrap(), "55312");
+    }
+
+    #[test]
+    fn test_blinks() {
+        let part = Part2::with_blinks(None, Some(75), None);
+        assert_eq!(part.solve_input("1").unwrap(), "34840149002654");
+
+        let part = Part2::with_blinks(None, None, Some(1_000_000_000));
+        assert_eq!(part.solve_input("1").unwrap(), "149002654");
+
+        let part = Part1::with_blinks(None, Some(1_000_000), Some(1_000_000_007));
+        assert!(part.solve_input("125 17").is_ok());
+
+        let (_, rules) = parse::parse_rules("n < 3 -> n + 1, n; * -> n").unwrap();
+        let part = Part1::with_blinks(Some(rules), Some(3), None);
+        assert_eq!(part.solve_input("0 5").unwrap(), "9");
+    }
+
+    #[test]
+    fn test_report() {
+        assert_eq!(
+            report_input("125 17", None, 6).unwrap(),
+            "15 distinct values, most common 2 x4, 0 x2, 6 x2\nno new values after blink 14 (76 values seen)"
+        );
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(Part2::solve_input("1").unwrap(), 34840149002654);
+        assert_eq!(Part2::default().solve_input("1").unwrap(), "34840149002654");
     }
 }
@@ -1,16 +1,109 @@
 use nom::{
-    character::complete::{space1, u64},
-    combinator::map,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{multispace0, multispace1, space0, space1, u64},
+    combinator::{all_consuming, map, opt, value},
     multi::separated_list1,
+    sequence::{delimited, preceded, separated_pair, tuple},
     IResult,
 };
 
-use super::stone::Stone;
+use super::{
+    rules::{Comparison, Condition, Output, Rule, RuleSet, Subject},
+    stone::Stone,
+};
 
 pub fn parse_input(input: &str) -> IResult<&str, Vec<Stone>> {
     separated_list1(space1, map(u64, Stone::new))(input)
 }
 
+pub fn parse_rules(input: &str) -> IResult<&str, RuleSet> {
+    // Rules go on separate lines, or are split by ';' on the command line
+    map(
+        separated_list1(
+            alt((delimited(space0, tag(";"), multispace0), multispace1)),
+            parse_rule,
+        ),
+        RuleSet::new,
+    )(input.trim())
+}
+
+pub fn parse_rule_set(input: &str) -> Result<RuleSet, String> {
+    // "n == 0 -> 1; * -> n", as given on the command line
+    all_consuming(parse_rules)(input.trim())
+        .map(|(_, rules)| rules)
+        .map_err(|_| format!("Invalid stone rules '{input}', expected RULE; RULE; ..."))
+}
+
+fn parse_rule(input: &str) -> IResult<&str, Rule> {
+    map(
+        separated_pair(
+            parse_condition,
+            delimited(space0, tag("->"), space0),
+            separated_list1(delimited(space0, tag(","), space0), parse_output),
+        ),
+        |(condition, outputs)| Rule { condition, outputs },
+    )(input)
+}
+
+fn parse_condition(input: &str) -> IResult<&str, Condition> {
+    alt((
+        value(Condition::Always, tag("*")),
+        map(
+            tuple((
+                parse_subject,
+                opt(preceded(delimited(space0, tag("%"), space0), u64)),
+                delimited(space0, parse_comparison, space0),
+                u64,
+            )),
+            |(subject, modulo, comparison, value)| Condition::Compare {
+                subject,
+                modulo,
+                comparison,
+                value,
+            },
+        ),
+    ))(input)
+}
+
+fn parse_subject(input: &str) -> IResult<&str, Subject> {
+    alt((
+        value(Subject::DigitCount, tag("digits")),
+        value(Subject::Number, tag("n")),
+    ))(input)
+}
+
+fn parse_comparison(input: &str) -> IResult<&str, Comparison> {
+    alt((
+        value(Comparison::Eq, tag("==")),
+        value(Comparison::Ne, tag("!=")),
+        value(Comparison::Le, tag("<=")),
+        value(Comparison::Ge, tag(">=")),
+        value(Comparison::Lt, tag("<")),
+        value(Comparison::Gt, tag(">")),
+    ))(input)
+}
+
+fn parse_output(input: &str) -> IResult<&str, Output> {
+    alt((
+        value(Output::Split, tag("split")),
+        map(
+            preceded(
+                tag("n"),
+                tuple((
+                    opt(preceded(delimited(space0, tag("*"), space0), u64)),
+                    opt(preceded(delimited(space0, tag("+"), space0), u64)),
+                )),
+            ),
+            |(multiplier, offset)| Output::Linear {
+                multiplier: multiplier.unwrap_or(1),
+                offset: offset.unwrap_or(0),
+            },
+        ),
+        map(u64, Output::Constant),
+    ))(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -21,4 +114,58 @@ mod tests {
         let expected = vec![Stone::new(123), Stone::new(456), Stone::new(789)];
         assert_eq!(parse_input(input), Ok(("", expected)));
     }
+
+    #[test]
+    fn test_parse_rules() {
+        let (rest, rules) =
+            parse_rules("n % 3 != 1 -> n * 2 + 1, 7\n\ndigits >= 4 -> split\n* -> n").unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            rules,
+            RuleSet::new(vec![
+                Rule {
+                    condition: Condition::Compare {
+                        subject: Subject::Number,
+                        modulo: Some(3),
+                        comparison: Comparison::Ne,
+                        value: 1,
+                    },
+                    outputs: vec![
+                        Output::Linear {
+                            multiplier: 2,
+                            offset: 1
+                        },
+                        Output::Constant(7)
+                    ],
+                },
+                Rule {
+                    condition: Condition::Compare {
+                        subject: Subject::DigitCount,
+                        modulo: None,
+                        comparison: Comparison::Ge,
+                        value: 4,
+                    },
+                    outputs: vec![Output::Split],
+                },
+                Rule {
+                    condition: Condition::Always,
+                    outputs: vec![Output::Linear {
+                        multiplier: 1,
+                        offset: 0
+                    }],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_rule_set() {
+        assert_eq!(
+            parse_rule_set("n == 0 -> 1; digits % 2 == 0 -> split;* -> n * 2024"),
+            Ok(RuleSet::default())
+        );
+        assert!(parse_rule_set("n == 0 -> 1; oops").is_err());
+        assert!(parse_rule_set("").is_err());
+    }
 }
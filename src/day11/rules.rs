@@ -0,0 +1,188 @@
+use std::error::Error;
+
+use super::{parse::parse_rules, stone::Stone};
+
+const DEFAULT_RULES: &str = "n == 0 -> 1
+digits % 2 == 0 -> split
+* -> n * 2024";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Subject {
+    Number,
+    DigitCount,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Condition {
+    Always,
+    Compare {
+        subject: Subject,
+        modulo: Option<u64>,
+        comparison: Comparison,
+        value: u64,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Output {
+    Constant(u64),
+    Linear { multiplier: u64, offset: u64 },
+    Split,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    pub condition: Condition,
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl Condition {
+    fn matches(&self, stone: Stone) -> Result<bool, Box<dyn Error>> {
+        let Condition::Compare {
+            subject,
+            modulo,
+            comparison,
+            value,
+        } = *self
+        else {
+            return Ok(true);
+        };
+
+        let actual = match subject {
+            Subject::Number => stone.number(),
+            Subject::DigitCount => stone.digit_count() as u64,
+        };
+
+        let actual = match modulo {
+            Some(m) => actual.checked_rem(m).ok_or("Division by zero")?,
+            None => actual,
+        };
+
+        Ok(match comparison {
+            Comparison::Eq => actual == value,
+            Comparison::Ne => actual != value,
+            Comparison::Lt => actual < value,
+            Comparison::Le => actual <= value,
+            Comparison::Gt => actual > value,
+            Comparison::Ge => actual >= value,
+        })
+    }
+}
+
+impl Output {
+    fn apply(&self, stone: Stone, stones: &mut Vec<Stone>) -> Result<(), Box<dyn Error>> {
+        match *self {
+            Output::Constant(number) => stones.push(Stone::new(number)),
+            Output::Linear { multiplier, offset } => stones.push(
+                stone
+                    .number()
+                    .checked_mul(multiplier)
+                    .and_then(|n| n.checked_add(offset))
+                    .map(Stone::new)
+                    .ok_or("Overflow")?,
+            ),
+            Output::Split => {
+                let (first, second) = stone.split();
+                stones.extend([first, second]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn apply(&self, stone: Stone) -> Result<Vec<Stone>, Box<dyn Error>> {
+        for rule in &self.rules {
+            if rule.condition.matches(stone)? {
+                let mut stones = Vec::with_capacity(rule.outputs.len());
+
+                for output in &rule.outputs {
+                    output.apply(stone, &mut stones)?;
+                }
+
+                return Ok(stones);
+            }
+        }
+
+        Ok(vec![stone])
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        let (_, rules) = parse_rules(DEFAULT_RULES).unwrap();
+        rules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules() {
+        let rules = RuleSet::default();
+
+        assert_eq!(rules.apply(Stone::new(0)).unwrap(), vec![Stone::new(1)]);
+        assert_eq!(rules.apply(Stone::new(1)).unwrap(), vec![Stone::new(2024)]);
+        assert_eq!(
+            rules.apply(Stone::new(1000)).unwrap(),
+            vec![Stone::new(10), Stone::new(0)]
+        );
+        assert!(rules.apply(Stone::new(u64::MAX / 10)).is_err());
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = RuleSet::new(vec![
+            Rule {
+                condition: Condition::Compare {
+                    subject: Subject::Number,
+                    modulo: Some(2),
+                    comparison: Comparison::Eq,
+                    value: 0,
+                },
+                outputs: vec![Output::Constant(1), Output::Constant(2)],
+            },
+            Rule {
+                condition: Condition::Compare {
+                    subject: Subject::DigitCount,
+                    modulo: None,
+                    comparison: Comparison::Gt,
+                    value: 2,
+                },
+                outputs: vec![Output::Linear {
+                    multiplier: 1,
+                    offset: 1,
+                }],
+            },
+        ]);
+
+        assert_eq!(
+            rules.apply(Stone::new(8)).unwrap(),
+            vec![Stone::new(1), Stone::new(2)]
+        );
+        assert_eq!(rules.apply(Stone::new(101)).unwrap(), vec![Stone::new(102)]);
+        assert_eq!(rules.apply(Stone::new(11)).unwrap(), vec![Stone::new(11)]);
+    }
+}
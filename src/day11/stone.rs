@@ -1,6 +1,8 @@
 use std::{error::Error, num::ParseIntError, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+use super::rules::RuleSet;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
 pub struct Stone {
     number: u64,
 }
@@ -10,27 +12,25 @@ impl Stone {
         Self { number }
     }
 
-    pub fn transform(&self) -> Result<Vec<Self>, Box<dyn Error>> {
-        if self.number == 0 {
-            return Ok(vec![Self::new(1)]);
-        }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
 
-        let number_str = self.to_string();
+    pub fn digit_count(&self) -> u32 {
+        self.number.checked_ilog10().unwrap_or(0) + 1
+    }
 
-        if number_str.len() % 2 == 0 {
-            let (first, second) = number_str.split_at(number_str.len() / 2);
+    pub fn split(&self) -> (Self, Self) {
+        let divisor = 10u64.pow(self.digit_count() - self.digit_count() / 2);
 
-            Ok([first, second]
-                .iter()
-                .map(|s| s.parse())
-                .collect::<Result<_, _>>()?)
-        } else {
-            Ok(vec![self.checked_mul(2024).ok_or("Overflow")?])
-        }
+        (
+            Self::new(self.number / divisor),
+            Self::new(self.number % divisor),
+        )
     }
 
-    fn checked_mul(&self, rhs: u64) -> Option<Self> {
-        self.number.checked_mul(rhs).map(Self::new)
+    pub fn transform(&self, rules: &RuleSet) -> Result<Vec<Self>, Box<dyn Error>> {
+        rules.apply(*self)
     }
 }
 
@@ -54,13 +54,29 @@ mod tests {
 
     #[test]
     fn test_transform_stone() {
-        assert_eq!(Stone::new(0).transform().unwrap(), vec![Stone::new(1)]);
+        let rules = RuleSet::default();
 
-        assert_eq!(Stone::new(1).transform().unwrap(), vec![Stone::new(2024)]);
+        assert_eq!(
+            Stone::new(0).transform(&rules).unwrap(),
+            vec![Stone::new(1)]
+        );
 
         assert_eq!(
-            Stone::new(2024).transform().unwrap(),
+            Stone::new(1).transform(&rules).unwrap(),
+            vec![Stone::new(2024)]
+        );
+
+        assert_eq!(
+            Stone::new(2024).transform(&rules).unwrap(),
             vec![Stone::new(20), Stone::new(24)]
         );
     }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(Stone::new(0).digit_count(), 1);
+        assert_eq!(Stone::new(1000).digit_count(), 4);
+        assert_eq!(Stone::new(1000).split(), (Stone::new(10), Stone::new(0)));
+        assert_eq!(Stone::new(123).split(), (Stone::new(1), Stone::new(23)));
+    }
 }
@@ -0,0 +1,80 @@
+use crate::util::{Direction, Point};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Adjacency {
+    #[default]
+    Orthogonal,
+    Diagonal,
+    Hexagonal,
+}
+
+impl Adjacency {
+    pub fn neighbors(&self, point: Point) -> Vec<Point> {
+        match self {
+            Adjacency::Orthogonal | Adjacency::Hexagonal => self.edge_neighbors(point),
+            Adjacency::Diagonal => Direction::all()
+                .flat_map(|direction| {
+                    [
+                        point + direction,
+                        point + direction + direction.rotate_clockwise(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    // Cells around the given one through which its neighbours can reach each other locally
+    pub fn ring(&self, point: Point) -> Vec<Point> {
+        match self {
+            Adjacency::Hexagonal => self.edge_neighbors(point),
+            Adjacency::Orthogonal | Adjacency::Diagonal => Adjacency::Diagonal.neighbors(point),
+        }
+    }
+
+    // Cells sharing an edge with the given cell, used for fences
+    pub fn edge_neighbors(&self, point: Point) -> Vec<Point> {
+        match self {
+            Adjacency::Orthogonal | Adjacency::Diagonal => Direction::all()
+                .map(|direction| point + direction)
+                .collect(),
+            Adjacency::Hexagonal => {
+                // Odd rows are shifted half a cell to the right
+                let shift = point.1.rem_euclid(2);
+
+                [
+                    Point(-1, 0),
+                    Point(1, 0),
+                    Point(shift - 1, -1),
+                    Point(shift, -1),
+                    Point(shift - 1, 1),
+                    Point(shift, 1),
+                ]
+                .into_iter()
+                .map(|offset| point + offset)
+                .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbors() {
+        assert_eq!(Adjacency::Orthogonal.neighbors(Point(0, 0)).len(), 4);
+        assert_eq!(Adjacency::Diagonal.neighbors(Point(0, 0)).len(), 8);
+        assert!(Adjacency::Diagonal
+            .neighbors(Point(0, 0))
+            .contains(&Point(1, 1)));
+
+        let even_row = Adjacency::Hexagonal.neighbors(Point(2, 2));
+        assert!(even_row.contains(&Point(1, 1)));
+        assert!(!even_row.contains(&Point(3, 1)));
+
+        let odd_row = Adjacency::Hexagonal.neighbors(Point(2, 1));
+        assert!(odd_row.contains(&Point(3, 0)));
+        assert!(!odd_row.contains(&Point(1, 0)));
+    }
+}
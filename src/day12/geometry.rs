@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::util::{Direction, Point};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        points.into_iter().fold(None, |bbox, Point(x, y)| {
+            Some(match bbox {
+                None => Self {
+                    min: Point(x, y),
+                    max: Point(x, y),
+                },
+                Some(Self { min, max }) => Self {
+                    min: Point(min.0.min(x), min.1.min(y)),
+                    max: Point(max.0.max(x), max.1.max(y)),
+                },
+            })
+        })
+    }
+
+    pub fn width(&self) -> u64 {
+        (self.max.0 - self.min.0 + 1) as u64
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.max.1 - self.min.1 + 1) as u64
+    }
+
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        self.min.0 <= other.min.0
+            && self.min.1 <= other.min.1
+            && self.max.0 >= other.max.0
+            && self.max.1 >= other.max.1
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Corners {
+    pub convex: u64,
+    pub concave: u64,
+}
+
+impl Corners {
+    pub fn count(points: &HashSet<Point>) -> Self {
+        let mut corners = Self::default();
+
+        for &point in points {
+            for direction in Direction::all() {
+                let next_direction = direction.rotate_clockwise();
+
+                let side_a = points.contains(&(point + direction));
+                let side_b = points.contains(&(point + next_direction));
+                let diagonal = points.contains(&(point + direction + next_direction));
+
+                if !side_a && !side_b {
+                    corners.convex += 1;
+                } else if side_a && side_b && !diagonal {
+                    corners.concave += 1;
+                }
+            }
+        }
+
+        corners
+    }
+
+    pub fn total(&self) -> u64 {
+        self.convex + self.concave
+    }
+
+    // Corners of a tile set meeting at one grid vertex, counted the same way as `count`
+    pub fn at_vertex(vertex: Point, contains: impl Fn(Point) -> bool) -> u64 {
+        let Point(x, y) = vertex;
+        let [nw, ne, sw, se] = [
+            Point(x - 1, y - 1),
+            Point(x, y - 1),
+            Point(x - 1, y),
+            Point(x, y),
+        ]
+        .map(contains);
+
+        match [nw, ne, sw, se].iter().filter(|&&c| c).count() {
+            1 | 3 => 1,
+            2 if nw == se => 2,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Polygon {
+    pub vertices: Vec<Point>,
+}
+
+impl Polygon {
+    // Twice the signed area; positive for clockwise loops in screen coordinates
+    fn signed_area_2x(&self) -> i64 {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum()
+    }
+
+    pub fn area(&self) -> u64 {
+        self.signed_area_2x().unsigned_abs() / 2
+    }
+
+    pub fn contains_tile(&self, tile: Point) -> bool {
+        let (px, py) = (2 * tile.0 + 1, 2 * tile.1 + 1);
+
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .filter(|(a, b)| a.0 == b.0 && 2 * a.0 > px)
+            .filter(|(a, b)| (2 * a.1 < py) != (2 * b.1 < py))
+            .count()
+            % 2
+            == 1
+    }
+
+    pub fn to_svg_path(&self) -> String {
+        let mut path = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, Point(x, y))| format!("{}{x} {y}", if i == 0 { "M" } else { "L" }))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        path.push_str(" Z");
+        path
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Outline {
+    pub outer: Polygon,
+    pub holes: Vec<Polygon>,
+}
+
+impl Outline {
+    // One outline per outer boundary loop, each with the holes directly inside it
+    pub fn trace(points: &HashSet<Point>) -> Vec<Self> {
+        let mut edges: HashMap<Point, Vec<Direction>> = HashMap::new();
+
+        for &point in points {
+            for direction in Direction::all() {
+                if !points.contains(&(point + direction)) {
+                    let (start, heading) = boundary_edge(point, direction);
+                    edges.entry(start).or_default().push(heading);
+                }
+            }
+        }
+
+        let mut starts = edges.keys().copied().collect::<Vec<_>>();
+        starts.sort();
+
+        let mut loops = Vec::new();
+
+        for start in starts {
+            while let Some(heading) = edges.get_mut(&start).and_then(|h| h.pop()) {
+                loops.push(trace_loop(&mut edges, start, heading));
+            }
+        }
+
+        let (outer, holes): (Vec<_>, Vec<_>) =
+            loops.into_iter().partition(|p| p.signed_area_2x() > 0);
+
+        let mut outlines = outer
+            .into_iter()
+            .map(|outer| Self {
+                outer,
+                holes: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        for hole in holes {
+            // The tile just inside the hole's first edge belongs to the enclosing piece
+            let tile = inner_tile(hole.vertices[0], hole.vertices[1]);
+
+            if let Some(outline) = outlines
+                .iter_mut()
+                .filter(|o| o.outer.contains_tile(tile))
+                .min_by_key(|o| o.outer.area())
+            {
+                outline.holes.push(hole);
+            }
+        }
+
+        outlines
+    }
+
+    pub fn to_svg_path(&self) -> String {
+        std::iter::once(&self.outer)
+            .chain(&self.holes)
+            .map(|p| p.to_svg_path())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// Clockwise edge along the side of a tile facing the given direction
+fn boundary_edge(point: Point, direction: Direction) -> (Point, Direction) {
+    let Point(x, y) = point;
+
+    match direction {
+        Direction::Up => (Point(x, y), Direction::Right),
+        Direction::Right => (Point(x + 1, y), Direction::Down),
+        Direction::Down => (Point(x + 1, y + 1), Direction::Left),
+        Direction::Left => (Point(x, y + 1), Direction::Up),
+    }
+}
+
+// Tile on the inside of a boundary edge, which is always to the right of its heading
+fn inner_tile(from: Point, to: Point) -> Point {
+    let Point(x, y) = from.min(to);
+
+    if from.1 == to.1 {
+        // Horizontal edges run right along a tile's top and left along its bottom
+        Point(x, if from.0 < to.0 { y } else { y - 1 })
+    } else {
+        Point(if from.1 < to.1 { x - 1 } else { x }, y)
+    }
+}
+
+fn trace_loop(
+    edges: &mut HashMap<Point, Vec<Direction>>,
+    start: Point,
+    start_heading: Direction,
+) -> Polygon {
+    let mut vertices = vec![start];
+    let mut pos = start + start_heading;
+    let mut heading = start_heading;
+
+    while pos != start {
+        let outgoing = edges.get_mut(&pos).unwrap();
+
+        let next_heading = [
+            heading.rotate_counterclockwise(),
+            heading,
+            heading.rotate_clockwise(),
+        ]
+        .into_iter()
+        .find(|h| outgoing.contains(h))
+        .unwrap();
+
+        outgoing.retain(|&h| h != next_heading);
+
+        if next_heading != heading {
+            vertices.push(pos);
+        }
+
+        heading = next_heading;
+        pos += heading;
+    }
+
+    if heading == start_heading {
+        vertices.remove(0);
+    }
+
+    Polygon { vertices }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: i64) -> HashSet<Point> {
+        (0..size)
+            .flat_map(|x| (0..size).map(move |y| Point(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let bbox = BoundingBox::from_points([Point(2, 3), Point(5, 1), Point(4, 4)]).unwrap();
+
+        assert_eq!(bbox.min, Point(2, 1));
+        assert_eq!(bbox.max, Point(5, 4));
+        assert_eq!((bbox.width(), bbox.height()), (4, 4));
+        assert_eq!(BoundingBox::from_points([]), None);
+    }
+
+    #[test]
+    fn test_corners() {
+        let l_shape = HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]);
+
+        assert_eq!(
+            Corners::count(&l_shape),
+            Corners {
+                convex: 5,
+                concave: 1
+            }
+        );
+
+        let mut ring = square(3);
+        ring.remove(&Point(1, 1));
+
+        assert_eq!(
+            Corners::count(&ring),
+            Corners {
+                convex: 4,
+                concave: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_corners_at_vertex() {
+        let mut ring = square(3);
+        ring.remove(&Point(1, 1));
+
+        let total = (0..=3)
+            .flat_map(|x| (0..=3).map(move |y| Point(x, y)))
+            .map(|vertex| Corners::at_vertex(vertex, |p| ring.contains(&p)))
+            .sum::<u64>();
+
+        assert_eq!(total, Corners::count(&ring).total());
+
+        let pinch = HashSet::from([Point(0, 0), Point(1, 1)]);
+        assert_eq!(Corners::at_vertex(Point(1, 1), |p| pinch.contains(&p)), 2);
+    }
+
+    #[test]
+    fn test_outline() {
+        let l_shape = HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]);
+        let outline = &Outline::trace(&l_shape)[0];
+
+        assert_eq!(outline.outer.vertices.len(), 6);
+        assert_eq!(outline.outer.area(), 3);
+        assert!(outline.holes.is_empty());
+
+        let mut ring = square(3);
+        ring.remove(&Point(1, 1));
+        let outlines = Outline::trace(&ring);
+        let outline = &outlines[0];
+
+        assert_eq!(outlines.len(), 1);
+
+        assert_eq!(
+            outline.outer.vertices,
+            vec![Point(0, 0), Point(3, 0), Point(3, 3), Point(0, 3)]
+        );
+        assert_eq!(outline.holes.len(), 1);
+        assert_eq!(outline.holes[0].area(), 1);
+        assert!(outline.holes[0].contains_tile(Point(1, 1)));
+        assert!(!outline.holes[0].contains_tile(Point(0, 1)));
+        assert_eq!(
+            outline.to_svg_path(),
+            "M0 0 L3 0 L3 3 L0 3 Z M1 1 L1 2 L2 2 L2 1 Z"
+        );
+    }
+
+    #[test]
+    fn test_outline_diagonal_holes() {
+        let mut shape = square(4);
+        shape.remove(&Point(1, 1));
+        shape.remove(&Point(2, 2));
+
+        let outlines = Outline::trace(&shape);
+
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[0].outer.vertices.len(), 4);
+        assert_eq!(outlines[0].holes.len(), 2);
+    }
+
+    #[test]
+    fn test_outline_separate_pieces() {
+        // A ring with an island in its hole, next to a lone tile
+        let mut shape = square(5);
+        shape.retain(|&Point(x, y)| x == 0 || y == 0 || x == 4 || y == 4);
+        shape.insert(Point(2, 2));
+        shape.insert(Point(7, 0));
+
+        let mut outlines = Outline::trace(&shape);
+        outlines.sort_by_key(|o| o.outer.area());
+
+        assert_eq!(outlines.len(), 3);
+        assert_eq!(
+            outlines.iter().map(|o| o.outer.area()).collect::<Vec<_>>(),
+            vec![1, 1, 25]
+        );
+        assert!(outlines[0].holes.is_empty());
+        assert!(outlines[1].holes.is_empty());
+        assert_eq!(outlines[2].holes.len(), 1);
+        assert_eq!(outlines[2].holes[0].area(), 9);
+
+        assert!(Outline::trace(&HashSet::new()).is_empty());
+    }
+}
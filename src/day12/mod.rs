@@ -1,49 +1,212 @@
+pub mod adjacency;
+mod geometry;
 mod parse;
 mod region;
+mod region_map;
 mod tile;
 
-use std::error::Error;
+use std::{collections::HashSet, error::Error};
 
+use adjacency::Adjacency;
 use parse::parse_input;
 use region::Region;
+use region_map::RegionMap;
+use tile::Tile;
 
 use crate::Puzzle;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+// Pixels per tile in the SVG map
+const SVG_SCALE: u64 = 10;
+
+// Single-tile repaints listed in the part 2 report
+const REPORT_REPAINTS: usize = 3;
+
+fn same_region(ignore_case: bool) -> fn(Tile, Tile) -> bool {
+    if ignore_case {
+        |a, b| a.eq_ignore_case(&b)
+    } else {
+        |a, b| a == b
+    }
+}
+
+#[derive(Default)]
+pub struct Part1 {
+    adjacency: Adjacency,
+    ignore_case: bool,
+}
 
 impl Part1 {
-    pub fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_regions(adjacency: Adjacency, ignore_case: bool) -> Self {
+        Self {
+            adjacency,
+            ignore_case,
+        }
+    }
+
+    pub fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
         let (_, map) = parse_input(input).map_err(|e| e.to_owned())?;
 
-        let regions = Region::find_regions(&map);
+        Ok(RegionMap::new_with(map, self.adjacency, same_region(self.ignore_case)).price_v1())
+    }
+
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let (_, map) = parse_input(input).map_err(|e| e.to_owned())?;
+
+        let mut regions =
+            Region::find_regions_with(&map, self.adjacency, same_region(self.ignore_case));
+        regions.sort_by_key(|r| r.points().iter().min().copied());
+
+        // Outlines, and with them holes and enclosing regions, need orthogonal adjacency and
+        // corners need square tiles, so whatever doesn't apply is reported as n/a
+        let parents = Region::find_parents(&regions).ok();
+
+        Ok(regions
+            .iter()
+            .enumerate()
+            .map(|(i, region)| {
+                let bbox = region.bounding_box();
+
+                let holes = region
+                    .hole_count()
+                    .map_or("n/a".to_string(), |holes| holes.to_string());
 
-        Ok(regions.iter().map(|r| r.price_v1()).sum())
+                let corners = region
+                    .corners()
+                    .map_or("n/a corners".to_string(), |corners| {
+                        format!(
+                            "{} corners ({} convex, {} concave)",
+                            corners.total(),
+                            corners.convex,
+                            corners.concave
+                        )
+                    });
+
+                let parent = match parents.as_ref().map(|parents| parents[i]) {
+                    Some(Some(p)) => {
+                        format!(
+                            "{} at {}",
+                            regions[p].tile(),
+                            regions[p].points().iter().min().unwrap()
+                        )
+                    }
+                    Some(None) => "nothing".to_string(),
+                    None => "n/a".to_string(),
+                };
+
+                format!(
+                    "{} at {}: area {}, price {}/{}, {}x{} box, {holes} holes, {corners}, inside {parent}",
+                    region.tile(),
+                    region.points().iter().min().unwrap(),
+                    region.points().len(),
+                    region.price_v1(),
+                    region.price_v2(),
+                    bbox.width(),
+                    bbox.height(),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    fn image_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let (_, map) = parse_input(input).map_err(|e| e.to_owned())?;
+        let regions =
+            Region::find_regions_with(&map, self.adjacency, same_region(self.ignore_case));
+
+        Region::to_svg(&regions, SVG_SCALE)
     }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
+    }
+
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.image_input(INPUT).map(Some)
     }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    adjacency: Adjacency,
+    ignore_case: bool,
+}
 
 impl Part2 {
-    pub fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_regions(adjacency: Adjacency, ignore_case: bool) -> Self {
+        Self {
+            adjacency,
+            ignore_case,
+        }
+    }
+
+    pub fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
         let (_, map) = parse_input(input).map_err(|e| e.to_owned())?;
 
-        let regions = Region::find_regions(&map);
+        Ok(RegionMap::new_with(map, self.adjacency, same_region(self.ignore_case)).price_v2())
+    }
+
+    // Repaints every tile in turn as each of its neighbours and lists the ones that
+    // lower the bulk price the most, with the region count and the size of the region the
+    // tile ends up in
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let (_, tiles) = parse_input(input).map_err(|e| e.to_owned())?;
 
-        Ok(regions.iter().map(|r| r.price_v2()).sum())
+        let mut points = tiles.keys().copied().collect::<Vec<_>>();
+        points.sort();
+
+        let mut map =
+            RegionMap::new_with(tiles.clone(), self.adjacency, same_region(self.ignore_case));
+        let mut repaints = Vec::new();
+
+        for point in points {
+            let tile = tiles[&point];
+
+            let candidates = self
+                .adjacency
+                .edge_neighbors(point)
+                .iter()
+                .filter_map(|neighbor| tiles.get(neighbor).copied())
+                .filter(|&t| t != tile)
+                .collect::<HashSet<_>>();
+
+            for candidate in candidates {
+                let delta = map.set_tile(point, candidate);
+                let region_count = map.region_count();
+                let area = map.region_at(point).map_or(0, |region| region.area());
+                map.set_tile(point, tile);
+
+                repaints.push((delta.v2, point, candidate, region_count, area));
+            }
+        }
+
+        repaints.sort_by_key(|&(delta, point, tile, _, _)| (delta, point, tile.to_string()));
+
+        Ok(repaints
+            .iter()
+            .take(REPORT_REPAINTS)
+            .map(|(delta, point, tile, region_count, area)| {
+                format!("{point} as {tile}: {delta:+}, {region_count} regions, joins area {area}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
     }
 }
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
     }
 }
 
@@ -59,17 +222,82 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(Part1::solve_input(TEST_INPUT).unwrap(), 140);
-        assert_eq!(Part1::solve_input(TEST_INPUT_2).unwrap(), 772);
-        assert_eq!(Part1::solve_input(TEST_INPUT_3).unwrap(), 1930);
+        assert_eq!(Part1::default().solve_input(TEST_INPUT).unwrap(), 140);
+        assert_eq!(Part1::default().solve_input(TEST_INPUT_2).unwrap(), 772);
+        assert_eq!(Part1::default().solve_input(TEST_INPUT_3).unwrap(), 1930);
+    }
+
+    #[test]
+    fn test_part1_report() {
+        let report = Part1::default().report_input(TEST_INPUT_2).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "O at 0,0: area 21, price 756/420, 5x5 box, 4 holes, 20 corners (4 convex, 16 concave), inside nothing"
+        );
+        assert_eq!(
+            lines[1],
+            "X at 1,1: area 1, price 4/4, 1x1 box, 0 holes, 4 corners (4 convex, 0 concave), inside O at 0,0"
+        );
+    }
+
+    #[test]
+    fn test_part1_report_adjacency() {
+        let report = Part1::with_regions(Adjacency::Diagonal, false)
+            .report_input(TEST_INPUT_2)
+            .unwrap();
+        assert_eq!(
+            report.lines().next(),
+            Some("O at 0,0: area 21, price 756/420, 5x5 box, n/a holes, 20 corners (4 convex, 16 concave), inside n/a")
+        );
+
+        let report = Part1::with_regions(Adjacency::Hexagonal, false)
+            .report_input(TEST_INPUT)
+            .unwrap();
+        assert!(report
+            .lines()
+            .all(|line| line.contains("n/a holes, n/a corners, inside n/a")));
+    }
+
+    #[test]
+    fn test_part1_image() {
+        let svg = Part1::default().image_input(TEST_INPUT).unwrap();
+
+        assert!(svg.contains("viewBox=\"0 0 4 4\" width=\"40\" height=\"40\""));
+        assert_eq!(svg.matches("<path ").count(), 5);
+    }
+
+    #[test]
+    fn test_part1_adjacency() {
+        let diagonal = Part1::with_regions(Adjacency::Diagonal, false);
+        assert_eq!(diagonal.solve_input("AB\nBA").unwrap(), 2 * 2 * 8);
+
+        let ignore_case = Part1::with_regions(Adjacency::Orthogonal, true);
+        assert_eq!(ignore_case.solve_input("Aa\nbB").unwrap(), 2 * 2 * 6);
+        assert!(Part1::with_regions(Adjacency::Hexagonal, false)
+            .image_input(TEST_INPUT)
+            .is_err());
+    }
+
+    #[test]
+    fn test_part2_report() {
+        let report = Part2::default().report_input(TEST_INPUT).unwrap();
+
+        // Repainting the C at 3,2 extends D and splits the rest of C into two rectangles
+        assert_eq!(
+            report.lines().next(),
+            Some("3,2 as D: -16, 6 regions, joins area 2")
+        );
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 80);
-        assert_eq!(Part2::solve_input(TEST_INPUT_2).unwrap(), 436);
-        assert_eq!(Part2::solve_input(TEST_INPUT_3).unwrap(), 1206);
-        assert_eq!(Part2::solve_input(TEST_INPUT_4).unwrap(), 236);
-        assert_eq!(Part2::solve_input(TEST_INPUT_5).unwrap(), 368);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT).unwrap(), 80);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT_2).unwrap(), 436);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT_3).unwrap(), 1206);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT_4).unwrap(), 236);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT_5).unwrap(), 368);
     }
 }
@@ -1,18 +1,44 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
+    error::Error,
     fmt::Display,
 };
 
-use super::tile::Tile;
+use super::{
+    adjacency::Adjacency,
+    geometry::{BoundingBox, Corners, Outline},
+    tile::Tile,
+};
 use crate::util::{Direction, Point};
 
 pub struct Region {
     points: HashSet<Point>,
     tile: Tile,
+    adjacency: Adjacency,
 }
 
 impl Region {
-    pub fn find_regions(map: &HashMap<Point, Tile>) -> Vec<Region> {
+    pub fn new(tile: Tile, points: HashSet<Point>, adjacency: Adjacency) -> Self {
+        Self {
+            points,
+            tile,
+            adjacency,
+        }
+    }
+
+    pub fn points(&self) -> &HashSet<Point> {
+        &self.points
+    }
+
+    pub fn points_mut(&mut self) -> &mut HashSet<Point> {
+        &mut self.points
+    }
+
+    pub fn find_regions_with(
+        map: &HashMap<Point, Tile>,
+        adjacency: Adjacency,
+        same_region: impl Fn(Tile, Tile) -> bool,
+    ) -> Vec<Region> {
         let mut processed_points = HashSet::new();
         let mut regions = Vec::new();
 
@@ -33,10 +59,12 @@ impl Region {
                 region_points.insert(current_point);
                 processed_points.insert(current_point);
 
-                for direction in Direction::all() {
-                    let neighbor_point = current_point + direction;
+                let current_tile = map[&current_point];
 
-                    if map.get(&neighbor_point) == Some(&tile)
+                for neighbor_point in adjacency.neighbors(current_point) {
+                    if map
+                        .get(&neighbor_point)
+                        .is_some_and(|&t| same_region(current_tile, t))
                         && !processed_points.contains(&neighbor_point)
                     {
                         queue.push_back(neighbor_point);
@@ -47,28 +75,36 @@ impl Region {
             regions.push(Self {
                 tile,
                 points: region_points,
+                adjacency,
             });
         }
 
         regions
     }
 
-    fn area(&self) -> u64 {
+    pub fn area(&self) -> u64 {
         self.points.len() as u64
     }
 
-    fn perimeter(&self) -> u64 {
+    pub fn perimeter(&self) -> u64 {
         self.points
             .iter()
             .map(|&point| {
-                Direction::all()
-                    .filter(|&direction| !self.points.contains(&(point + direction)))
+                self.adjacency
+                    .edge_neighbors(point)
+                    .into_iter()
+                    .filter(|neighbor| !self.points.contains(neighbor))
                     .count() as u64
             })
             .sum()
     }
 
-    fn sides(&self) -> u64 {
+    pub fn sides(&self) -> u64 {
+        // No two boundary edges of a hexagonal region are collinear
+        if self.adjacency == Adjacency::Hexagonal {
+            return self.perimeter();
+        }
+
         let mut points_vec = Vec::from_iter(self.points.iter().copied());
         points_vec.sort();
 
@@ -104,6 +140,108 @@ impl Region {
     pub fn price_v2(&self) -> u64 {
         self.area() * self.sides()
     }
+
+    pub fn tile(&self) -> Tile {
+        self.tile
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(self.points.iter().copied()).unwrap()
+    }
+
+    // Corners of the union of square tiles, which hexagonal tiles don't have
+    pub fn corners(&self) -> Result<Corners, Box<dyn Error>> {
+        if self.adjacency == Adjacency::Hexagonal {
+            return Err("Corners need square tiles".into());
+        }
+
+        Ok(Corners::count(&self.points))
+    }
+
+    // Outlines are traced along square tile edges, and only orthogonal regions are
+    // guaranteed to come out as one simple polygon per boundary loop
+    pub fn outlines(&self) -> Result<Vec<Outline>, Box<dyn Error>> {
+        if self.adjacency != Adjacency::Orthogonal {
+            return Err(format!(
+                "Outlines need orthogonal adjacency, not {:?}",
+                self.adjacency
+            )
+            .into());
+        }
+
+        Ok(Outline::trace(&self.points))
+    }
+
+    pub fn hole_count(&self) -> Result<usize, Box<dyn Error>> {
+        Ok(self.outlines()?.iter().map(|o| o.holes.len()).sum())
+    }
+
+    pub fn find_parents(regions: &[Region]) -> Result<Vec<Option<usize>>, Box<dyn Error>> {
+        let outlines = regions
+            .iter()
+            .map(|r| r.outlines())
+            .collect::<Result<Vec<_>, _>>()?;
+        let bounding_boxes = regions.iter().map(|r| r.bounding_box()).collect::<Vec<_>>();
+
+        Ok(regions
+            .iter()
+            .enumerate()
+            .map(|(i, region)| {
+                let &point = region.points.iter().next().unwrap();
+
+                (0..regions.len())
+                    .filter(|&j| j != i && bounding_boxes[j].contains(&bounding_boxes[i]))
+                    .filter_map(|j| {
+                        outlines[j]
+                            .iter()
+                            .find(|o| o.holes.iter().any(|h| h.contains_tile(point)))
+                            .map(|o| (o.outer.area(), j))
+                    })
+                    .min()
+                    .map(|(_, j)| j)
+            })
+            .collect())
+    }
+
+    pub fn to_svg(regions: &[Region], scale: u64) -> Result<String, Box<dyn Error>> {
+        let bbox = BoundingBox::from_points(
+            regions
+                .iter()
+                .flat_map(|r| [r.bounding_box().min, r.bounding_box().max]),
+        )
+        .unwrap_or(BoundingBox {
+            min: Point(0, 0),
+            max: Point(0, 0),
+        });
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+            bbox.min.0,
+            bbox.min.1,
+            bbox.width(),
+            bbox.height(),
+            bbox.width() * scale,
+            bbox.height() * scale,
+        );
+
+        for region in regions {
+            let tile = region.tile.to_string();
+            let hue = tile.chars().map(|c| c as u64 * 47).sum::<u64>() % 360;
+
+            svg.push_str(&format!(
+                "  <path d=\"{}\" fill=\"hsl({hue}, 60%, 70%)\" fill-rule=\"evenodd\" stroke=\"black\" stroke-width=\"0.05\"><title>{tile}</title></path>\n",
+                region
+                    .outlines()?
+                    .iter()
+                    .map(|o| o.to_svg_path())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
 }
 
 impl Display for Region {
@@ -114,6 +252,7 @@ impl Display for Region {
 
 #[cfg(test)]
 mod tests {
+    use super::super::parse::parse_input;
     use super::*;
 
     #[test]
@@ -121,7 +260,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0)])
+                points: HashSet::from([Point(0, 0)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .area(),
             1
@@ -130,7 +270,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .area(),
             2
@@ -139,7 +280,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .area(),
             3
@@ -151,7 +293,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0)])
+                points: HashSet::from([Point(0, 0)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .perimeter(),
             4
@@ -160,7 +303,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .perimeter(),
             6
@@ -169,7 +313,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .perimeter(),
             8
@@ -181,7 +326,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0)])
+                points: HashSet::from([Point(0, 0)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .sides(),
             4
@@ -190,7 +336,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .sides(),
             4
@@ -199,7 +346,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .sides(),
             6
@@ -211,7 +359,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0)])
+                points: HashSet::from([Point(0, 0)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v1(),
             4
@@ -220,7 +369,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v1(),
             2 * 6
@@ -229,19 +379,118 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v1(),
             3 * 8
         );
     }
 
+    #[test]
+    fn test_geometry() {
+        let (_, map) = parse_input(include_str!("test-input-2.txt")).unwrap();
+        let regions = Region::find_regions_with(&map, Adjacency::Orthogonal, |a, b| a == b);
+
+        let (outer_index, outer) = regions
+            .iter()
+            .enumerate()
+            .find(|(_, r)| r.tile() == Tile::new('O'))
+            .unwrap();
+
+        assert_eq!(outer.hole_count().unwrap(), 4);
+        assert_eq!(outer.corners().unwrap().total(), outer.sides());
+        assert_eq!(
+            outer.bounding_box(),
+            BoundingBox {
+                min: Point(0, 0),
+                max: Point(4, 4)
+            }
+        );
+
+        let parents = Region::find_parents(&regions).unwrap();
+
+        for (region, parent) in regions.iter().zip(parents) {
+            if region.tile() == Tile::new('X') {
+                assert_eq!(parent, Some(outer_index));
+            } else {
+                assert_eq!(parent, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nested_parents() {
+        let (_, map) = parse_input("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA").unwrap();
+        let regions = Region::find_regions_with(&map, Adjacency::Orthogonal, |a, b| a == b);
+        let parents = Region::find_parents(&regions).unwrap();
+
+        let index_of = |c| regions.iter().position(|r| r.tile() == Tile::new(c));
+        let (a, b, c) = (index_of('A'), index_of('B'), index_of('C'));
+
+        assert_eq!(parents[a.unwrap()], None);
+        assert_eq!(parents[b.unwrap()], a);
+        assert_eq!(parents[c.unwrap()], b);
+    }
+
+    #[test]
+    fn test_custom_adjacency() {
+        let (_, map) = parse_input("AB\nBA").unwrap();
+
+        assert_eq!(
+            Region::find_regions_with(&map, Adjacency::Orthogonal, |a, b| a == b).len(),
+            4
+        );
+
+        let regions = Region::find_regions_with(&map, Adjacency::Diagonal, |a, b| a == b);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].perimeter(), 8);
+        assert_eq!(regions[0].sides(), 8);
+
+        assert!(regions[0].outlines().is_err());
+        assert_eq!(regions[0].corners().unwrap().convex, 8);
+
+        let regions = Region::find_regions_with(&map, Adjacency::Hexagonal, |a, b| a == b);
+        assert_eq!(regions.len(), 3);
+        assert!(Region::to_svg(&regions, 10).is_err());
+        assert!(regions[0].corners().is_err());
+        assert_eq!(
+            regions.iter().map(|r| r.price_v1()).sum::<u64>(),
+            6 + 6 + 2 * 10
+        );
+        assert_eq!(
+            regions.iter().map(|r| r.price_v2()).sum::<u64>(),
+            regions.iter().map(|r| r.price_v1()).sum::<u64>()
+        );
+
+        let (_, map) = parse_input("Aa\nbB").unwrap();
+        let regions =
+            Region::find_regions_with(&map, Adjacency::Orthogonal, |a, b| a.eq_ignore_case(&b));
+        assert_eq!(regions.len(), 2);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let regions = vec![Region {
+            tile: Tile::new('A'),
+            points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+            adjacency: Adjacency::Orthogonal,
+        }];
+
+        let svg = Region::to_svg(&regions, 10).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 2 2\" width=\"20\" height=\"20\">"));
+        assert!(svg.contains("<title>A</title>"));
+        assert_eq!(svg.matches(" Z").count(), 1);
+    }
+
     #[test]
     fn test_region_price_v2() {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0)])
+                points: HashSet::from([Point(0, 0)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v2(),
             4
@@ -250,7 +499,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v2(),
             2 * 4
@@ -259,7 +509,8 @@ mod tests {
         assert_eq!(
             Region {
                 tile: Tile::new('A'),
-                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)])
+                points: HashSet::from([Point(0, 0), Point(0, 1), Point(1, 1)]),
+                adjacency: Adjacency::Orthogonal,
             }
             .price_v2(),
             3 * 6
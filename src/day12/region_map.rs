@@ -0,0 +1,365 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{adjacency::Adjacency, geometry::Corners, region::Region, tile::Tile};
+use crate::util::Point;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct PriceDelta {
+    pub v1: i64,
+    pub v2: i64,
+}
+
+pub struct RegionMap {
+    tiles: HashMap<Point, Tile>,
+    adjacency: Adjacency,
+    same_region: fn(Tile, Tile) -> bool,
+    region_ids: HashMap<Point, usize>,
+    // Each region with its perimeter and side count
+    regions: HashMap<usize, (Region, u64, u64)>,
+    next_id: usize,
+    price_v1: u64,
+    price_v2: u64,
+}
+
+impl RegionMap {
+    // `same_region` has to be an equivalence, so that regions which never touch stay
+    // separate and merging them only changes the fences around the new tile
+    pub fn new_with(
+        tiles: HashMap<Point, Tile>,
+        adjacency: Adjacency,
+        same_region: fn(Tile, Tile) -> bool,
+    ) -> Self {
+        let mut map = Self {
+            tiles,
+            adjacency,
+            same_region,
+            region_ids: HashMap::new(),
+            regions: HashMap::new(),
+            next_id: 0,
+            price_v1: 0,
+            price_v2: 0,
+        };
+
+        for region in Region::find_regions_with(&map.tiles, adjacency, same_region) {
+            map.add_region(region);
+        }
+
+        map
+    }
+
+    pub fn price_v1(&self) -> u64 {
+        self.price_v1
+    }
+
+    pub fn price_v2(&self) -> u64 {
+        self.price_v2
+    }
+
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn region_at(&self, point: Point) -> Option<&Region> {
+        let id = self.region_ids.get(&point)?;
+        self.regions.get(id).map(|(region, _, _)| region)
+    }
+
+    // Fences and sides are updated from the tiles around the changed one; a region is
+    // only flooded again when losing the tile may have cut it in two
+    pub fn set_tile(&mut self, point: Point, tile: Tile) -> PriceDelta {
+        let (price_v1, price_v2) = (self.price_v1, self.price_v2);
+
+        if self.tiles.insert(point, tile) == Some(tile) {
+            return PriceDelta::default();
+        }
+
+        if let Some(id) = self.region_ids.remove(&point) {
+            self.detach(id, point);
+        }
+
+        self.attach(point, tile);
+
+        PriceDelta {
+            v1: self.price_v1 as i64 - price_v1 as i64,
+            v2: self.price_v2 as i64 - price_v2 as i64,
+        }
+    }
+
+    fn detach(&mut self, id: usize, point: Point) {
+        let (mut region, perimeter, sides) = self.take_region(id);
+
+        region.points_mut().remove(&point);
+        let (perimeter_growth, sides_growth) = self.growth(region.points(), point);
+        let perimeter = (perimeter as i64 - perimeter_growth) as u64;
+        let sides = (sides as i64 - sides_growth) as u64;
+
+        let seeds = self
+            .adjacency
+            .neighbors(point)
+            .into_iter()
+            .filter(|neighbor| region.points().contains(neighbor))
+            .collect::<Vec<_>>();
+
+        if seeds.is_empty() {
+            return;
+        }
+
+        if self.stays_connected(region.points(), point, &seeds) {
+            self.put_region(id, region, perimeter, sides);
+            return;
+        }
+
+        for point in region.points() {
+            self.region_ids.remove(point);
+        }
+
+        for component in split_components(region.points().clone(), &seeds, self.adjacency) {
+            self.add_region(Region::new(region.tile(), component, self.adjacency));
+        }
+    }
+
+    fn attach(&mut self, point: Point, tile: Tile) {
+        let merged_ids = self
+            .adjacency
+            .neighbors(point)
+            .into_iter()
+            .filter(|neighbor| {
+                self.tiles
+                    .get(neighbor)
+                    .is_some_and(|&t| (self.same_region)(tile, t))
+            })
+            .filter_map(|neighbor| self.region_ids.get(&neighbor).copied())
+            .collect::<HashSet<_>>();
+
+        // Grow the largest region so the fewest tiles change id
+        let Some(&base_id) = merged_ids
+            .iter()
+            .max_by_key(|id| (self.regions[id].0.area(), **id))
+        else {
+            let region = Region::new(tile, HashSet::from([point]), self.adjacency);
+            self.add_region(region);
+            return;
+        };
+
+        let (mut region, mut perimeter, mut sides) = self.take_region(base_id);
+
+        for &id in merged_ids.iter().filter(|&&id| id != base_id) {
+            let (other, other_perimeter, other_sides) = self.take_region(id);
+
+            for &other_point in other.points() {
+                self.region_ids.insert(other_point, base_id);
+            }
+
+            region.points_mut().extend(other.points());
+            perimeter += other_perimeter;
+            sides += other_sides;
+        }
+
+        let (perimeter_growth, sides_growth) = self.growth(region.points(), point);
+        let perimeter = (perimeter as i64 + perimeter_growth) as u64;
+        let sides = (sides as i64 + sides_growth) as u64;
+
+        region.points_mut().insert(point);
+        self.region_ids.insert(point, base_id);
+
+        self.put_region(base_id, region, perimeter, sides);
+    }
+
+    // How much the perimeter and side count grow when `point` joins `points`
+    fn growth(&self, points: &HashSet<Point>, point: Point) -> (i64, i64) {
+        let neighbors = self.adjacency.edge_neighbors(point);
+        let inside = neighbors.iter().filter(|n| points.contains(n)).count() as i64;
+
+        let perimeter = neighbors.len() as i64 - 2 * inside;
+
+        let sides = match self.adjacency {
+            // No two boundary edges of a hexagonal region are collinear
+            Adjacency::Hexagonal => perimeter,
+            _ => [Point(0, 0), Point(1, 0), Point(0, 1), Point(1, 1)]
+                .map(|offset| point + offset)
+                .iter()
+                .map(|&vertex| {
+                    let after = Corners::at_vertex(vertex, |p| p == point || points.contains(&p));
+                    let before = Corners::at_vertex(vertex, |p| points.contains(&p));
+
+                    after as i64 - before as i64
+                })
+                .sum(),
+        };
+
+        (perimeter, sides)
+    }
+
+    // Whether the neighbours left behind by a removed tile still form one piece, checked
+    // around the tile first and over the whole region only when that is inconclusive
+    fn stays_connected(&self, points: &HashSet<Point>, removed: Point, seeds: &[Point]) -> bool {
+        let ring = self
+            .adjacency
+            .ring(removed)
+            .into_iter()
+            .filter(|p| points.contains(p))
+            .collect::<HashSet<_>>();
+
+        if reaches_all(&ring, seeds, self.adjacency) {
+            return true;
+        }
+
+        reaches_all(points, seeds, self.adjacency)
+    }
+
+    fn add_region(&mut self, region: Region) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        for &point in region.points() {
+            self.region_ids.insert(point, id);
+        }
+
+        let (perimeter, sides) = (region.perimeter(), region.sides());
+        self.put_region(id, region, perimeter, sides);
+    }
+
+    fn put_region(&mut self, id: usize, region: Region, perimeter: u64, sides: u64) {
+        self.price_v1 += region.area() * perimeter;
+        self.price_v2 += region.area() * sides;
+
+        self.regions.insert(id, (region, perimeter, sides));
+    }
+
+    // Removes a region's price, leaving its tiles' ids to the caller
+    fn take_region(&mut self, id: usize) -> (Region, u64, u64) {
+        let (region, perimeter, sides) = self.regions.remove(&id).unwrap();
+
+        self.price_v1 -= region.area() * perimeter;
+        self.price_v2 -= region.area() * sides;
+
+        (region, perimeter, sides)
+    }
+}
+
+// Whether a flood from the first seed within `points` reaches all the others
+fn reaches_all(points: &HashSet<Point>, seeds: &[Point], adjacency: Adjacency) -> bool {
+    let mut remaining = seeds[1..].iter().copied().collect::<HashSet<_>>();
+    let mut visited = HashSet::from([seeds[0]]);
+    let mut queue = VecDeque::from([seeds[0]]);
+
+    while let Some(current) = queue.pop_front() {
+        if remaining.is_empty() {
+            return true;
+        }
+
+        for neighbor in adjacency.neighbors(current) {
+            if points.contains(&neighbor) && visited.insert(neighbor) {
+                remaining.remove(&neighbor);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    remaining.is_empty()
+}
+
+fn split_components(
+    mut points: HashSet<Point>,
+    seeds: &[Point],
+    adjacency: Adjacency,
+) -> Vec<HashSet<Point>> {
+    let mut components = Vec::new();
+
+    for &seed in seeds {
+        if !points.remove(&seed) {
+            continue;
+        }
+
+        let mut component = HashSet::from([seed]);
+        let mut queue = VecDeque::from([seed]);
+
+        while let Some(current) = queue.pop_front() {
+            for neighbor in adjacency.neighbors(current) {
+                if points.remove(&neighbor) {
+                    component.insert(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        components.push(component);
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse::parse_input;
+    use super::*;
+
+    const TEST_INPUT_3: &str = include_str!("test-input-3.txt");
+
+    #[test]
+    fn test_set_tile() {
+        let (_, tiles) = parse_input("AAA\nABA\nAAA").unwrap();
+        let mut map = RegionMap::new_with(tiles, Adjacency::Orthogonal, |a, b| a == b);
+
+        assert_eq!((map.price_v1(), map.price_v2()), (8 * 16 + 4, 8 * 8 + 4));
+
+        let delta = map.set_tile(Point(1, 1), Tile::new('A'));
+        assert_eq!(map.region_count(), 1);
+        assert_eq!((map.price_v1(), map.price_v2()), (9 * 12, 9 * 4));
+        assert_eq!(
+            delta,
+            PriceDelta {
+                v1: 108 - 132,
+                v2: 36 - 68
+            }
+        );
+
+        map.set_tile(Point(1, 0), Tile::new('B'));
+        map.set_tile(Point(1, 2), Tile::new('B'));
+        map.set_tile(Point(1, 1), Tile::new('B'));
+        assert_eq!(map.region_count(), 3);
+        assert_eq!(map.region_at(Point(1, 0)).unwrap().points().len(), 3);
+
+        assert_eq!(
+            map.set_tile(Point(1, 1), Tile::new('B')),
+            PriceDelta::default()
+        );
+    }
+
+    #[test]
+    fn test_set_tile_matches_full_recompute() {
+        let letters = ['R', 'I', 'r', 'C', 'i'];
+        let predicates: [fn(Tile, Tile) -> bool; 2] = [|a, b| a == b, |a, b| a.eq_ignore_case(&b)];
+
+        for adjacency in [
+            Adjacency::Orthogonal,
+            Adjacency::Diagonal,
+            Adjacency::Hexagonal,
+        ] {
+            for same_region in predicates {
+                let (_, mut tiles) = parse_input(TEST_INPUT_3).unwrap();
+                let mut map = RegionMap::new_with(tiles.clone(), adjacency, same_region);
+
+                // Sweep the grid twice with a pattern that keeps splitting and merging regions
+                for round in 0..2 {
+                    for (x, y) in (0..10).flat_map(|x| (0..10).map(move |y| (x, y))) {
+                        let point = Point(x, y);
+                        let tile = Tile::new(letters[((x * 3 + y * 7 + round) % 5) as usize]);
+
+                        let before = (map.price_v1() as i64, map.price_v2() as i64);
+                        let delta = map.set_tile(point, tile);
+                        assert_eq!(before.0 + delta.v1, map.price_v1() as i64);
+                        assert_eq!(before.1 + delta.v2, map.price_v2() as i64);
+
+                        tiles.insert(point, tile);
+                        let regions = Region::find_regions_with(&tiles, adjacency, same_region);
+
+                        assert_eq!(map.region_count(), regions.len());
+                        assert_eq!(map.price_v1(), regions.iter().map(|r| r.price_v1()).sum());
+                        assert_eq!(map.price_v2(), regions.iter().map(|r| r.price_v2()).sum());
+                    }
+                }
+            }
+        }
+    }
+}
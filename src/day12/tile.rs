@@ -7,6 +7,10 @@ impl Tile {
     pub fn new(c: char) -> Self {
         Self(c)
     }
+
+    pub fn eq_ignore_case(&self, other: &Tile) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
 }
 
 impl Display for Tile {
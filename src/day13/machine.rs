@@ -1,42 +1,562 @@
 use crate::util::Point;
 
+// Tokens per press when neither the input nor the command line gives a cost
+const DEFAULT_COST_A: u64 = 3;
+const DEFAULT_COST: u64 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Button {
+    pub name: String,
+    pub offset: Point,
+    pub cost: u64,
+}
+
+impl Button {
+    pub fn new(name: &str, offset: Point, cost: Option<u64>) -> Self {
+        let default_cost = if name == "A" {
+            DEFAULT_COST_A
+        } else {
+            DEFAULT_COST
+        };
+
+        Self {
+            name: name.to_string(),
+            offset,
+            cost: cost.unwrap_or(default_cost),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Machine {
-    button_a: Point,
-    button_b: Point,
+    buttons: Vec<Button>,
     price: Point,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Solution {
+    // Presses per button, in the machine's button order
+    pub presses: Vec<u64>,
+    pub cost: u64,
+}
+
 impl Machine {
-    pub fn new(button_a: Point, button_b: Point, price: Point) -> Self {
-        Self {
-            button_a,
-            button_b,
-            price,
+    pub fn new(buttons: Vec<Button>, price: Point) -> Self {
+        Self { buttons, price }
+    }
+
+    pub fn offset_price(&mut self, offset: Point) {
+        self.price += offset;
+    }
+
+    pub fn set_cost(&mut self, name: &str, cost: u64) {
+        for button in self.buttons.iter_mut().filter(|b| b.name == name) {
+            button.cost = cost;
         }
     }
 
-    pub fn increase_prices(&mut self) {
-        self.price.0 += 10000000000000;
-        self.price.1 += 10000000000000;
+    pub fn solve(&self) -> Option<Solution> {
+        self.solve_limited(None)
     }
 
-    pub fn solve(&self) -> u64 {
-        let Point(bax, bay) = self.button_a;
-        let Point(bbx, bby) = self.button_b;
-        let Point(px, py) = self.price;
+    // Cheapest way to reach the prize, pressing each button at most `max_presses` times
+    pub fn solve_limited(&self, max_presses: Option<u64>) -> Option<Solution> {
+        let price = (self.price.0 as i128, self.price.1 as i128);
+        let max_presses = max_presses.map(|m| m as i128);
+
+        let presses = match &self.buttons[..] {
+            [] => (price == (0, 0)).then(Vec::new)?,
+            [a] => vec![solve_single(a, price, max_presses)?],
+            [a, b] => {
+                let (n, m) = solve_pair(a, b, price, max_presses)?;
+                vec![n, m]
+            }
+            buttons => System::new(buttons, price)?.solve(max_presses)?,
+        };
+
+        let cost = presses
+            .iter()
+            .zip(&self.buttons)
+            .map(|(&n, button)| n * button.cost as i128)
+            .sum::<i128>();
+
+        Some(Solution {
+            presses: presses.into_iter().map(|p| p as u64).collect(),
+            cost: cost as u64,
+        })
+    }
+}
+
+// Integer program for three or more buttons. The prize gives at most two independent
+// equations, so an optimal vertex of the LP relaxation has at most two basic buttons.
+// By the proximity theorem of Cook, Gerards, Schrijver and Tardos, some integer optimum
+// lies within n * delta of that vertex in every coordinate, where delta is the largest
+// subdeterminant of the equations. Only the non-basic buttons are enumerated inside that
+// box, with the basic ones solved exactly, so the work is O((2 * n * delta)^(n - rank))
+struct System {
+    // One or two independent equations over the button presses
+    rows: Vec<Vec<i128>>,
+    rhs: Vec<i128>,
+    costs: Vec<i128>,
+}
+
+struct Vertex {
+    basis: Vec<usize>,
+    // Signed determinant of the basic columns
+    det: i128,
+    // Non-basic presses; basic entries are unused
+    values: Vec<i128>,
+    // Cost of the vertex times |det|
+    scaled_cost: i128,
+}
+
+impl System {
+    // None if the prize is off the line spanned by collinear buttons
+    fn new(buttons: &[Button], price: (i128, i128)) -> Option<Self> {
+        let xs = buttons
+            .iter()
+            .map(|b| b.offset.0 as i128)
+            .collect::<Vec<_>>();
+        let ys = buttons
+            .iter()
+            .map(|b| b.offset.1 as i128)
+            .collect::<Vec<_>>();
+        let costs = buttons.iter().map(|b| b.cost as i128).collect();
+
+        let independent = (0..buttons.len())
+            .any(|i| (i + 1..buttons.len()).any(|j| xs[i] * ys[j] - ys[i] * xs[j] != 0));
+
+        let (rows, rhs) = if independent {
+            (vec![xs, ys], vec![price.0, price.1])
+        } else if let Some(i) = (0..buttons.len()).find(|&i| xs[i] != 0 || ys[i] != 0) {
+            if xs[i] * price.1 - ys[i] * price.0 != 0 {
+                return None;
+            }
 
-        if let Some(b) = div_without_remainder(py * bax - px * bay, bby * bax - bbx * bay) {
-            if let Some(a) = div_without_remainder(px - bbx * b, bax) {
-                return a as u64 * 3 + b as u64;
+            // Every button is a multiple of the same direction, so one axis says it all
+            if xs.iter().any(|&x| x != 0) {
+                (vec![xs], vec![price.0])
+            } else {
+                (vec![ys], vec![price.1])
             }
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        if rows.is_empty() && price != (0, 0) {
+            return None;
+        }
+
+        Some(Self { rows, rhs, costs })
+    }
+
+    fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    fn solve(&self, limit: Option<i128>) -> Option<Vec<i128>> {
+        if self.rows.is_empty() {
+            return Some(vec![0; self.len()]);
+        }
+
+        let vertex = self.optimal_vertex(limit)?;
+        let det = vertex.det.abs();
+        let radius = self.len() as i128 * self.max_subdeterminant();
+
+        let free = (0..self.len())
+            .filter(|k| !vertex.basis.contains(k))
+            .collect::<Vec<_>>();
+
+        let ranges = free
+            .iter()
+            .map(|&k| {
+                let value = vertex.values[k];
+                let hi = limit.map_or(value + radius, |l| l.min(value + radius));
+                ((value - radius).max(0), hi)
+            })
+            .collect::<Vec<_>>();
+
+        // Exact change in cost (times |det|) per press of each non-basic button
+        let reduced = free
+            .iter()
+            .map(|&k| {
+                let column = self.rows.iter().map(|row| row[k]).collect::<Vec<_>>();
+                let through_basis = self.solve_basis(&vertex.basis, vertex.det, &column);
+
+                self.costs[k] * det
+                    - vertex
+                        .basis
+                        .iter()
+                        .zip(through_basis)
+                        .map(|(&b, y)| self.costs[b] * y)
+                        .sum::<i128>()
+            })
+            .collect::<Vec<_>>();
+
+        // Lowest possible contribution of the buttons from each index onwards
+        let mut rest_min = vec![0; free.len() + 1];
+        for i in (0..free.len()).rev() {
+            let (lo, hi) = ranges[i];
+            let value = vertex.values[free[i]];
+            rest_min[i] =
+                rest_min[i + 1] + (reduced[i] * (lo - value)).min(reduced[i] * (hi - value));
+        }
+
+        let mut search = Search {
+            system: self,
+            vertex: &vertex,
+            limit,
+            free,
+            ranges,
+            reduced,
+            rest_min,
+            best: None,
+        };
+
+        let mut values = vertex.values.clone();
+        search.visit(0, &mut values, 0);
+
+        search.best.map(|(_, presses)| presses)
+    }
+
+    // Cheapest basic solution of the relaxation, with every non-basic button at a bound
+    fn optimal_vertex(&self, limit: Option<i128>) -> Option<Vertex> {
+        let mut best: Option<Vertex> = None;
+
+        for (basis, d) in self.bases() {
+            let det = d.abs();
+            let free = (0..self.len())
+                .filter(|k| !basis.contains(k))
+                .collect::<Vec<_>>();
+
+            let assignments = if limit.is_some() { 1 << free.len() } else { 1 };
+
+            for mask in 0..assignments {
+                let mut values = vec![0; self.len()];
+                for (bit, &k) in free.iter().enumerate() {
+                    if mask >> bit & 1 == 1 {
+                        values[k] = limit.unwrap_or(0);
+                    }
+                }
+
+                let basic = self.basic_scaled(&basis, d, &values);
+                if basic
+                    .iter()
+                    .any(|&x| x < 0 || limit.is_some_and(|l| x > l * det))
+                {
+                    continue;
+                }
+
+                let scaled_cost = free
+                    .iter()
+                    .map(|&k| self.costs[k] * values[k] * det)
+                    .sum::<i128>()
+                    + basis
+                        .iter()
+                        .zip(&basic)
+                        .map(|(&b, &x)| self.costs[b] * x)
+                        .sum::<i128>();
+
+                let better = best
+                    .as_ref()
+                    .is_none_or(|other| scaled_cost * other.det.abs() < other.scaled_cost * det);
+
+                if better {
+                    best = Some(Vertex {
+                        basis: basis.clone(),
+                        det: d,
+                        values,
+                        scaled_cost,
+                    });
+                }
+            }
+        }
+
+        best
+    }
+
+    // Sets of as many buttons as there are equations, with independent columns
+    fn bases(&self) -> Vec<(Vec<usize>, i128)> {
+        let n = self.len();
+
+        match &self.rows[..] {
+            [row] => (0..n)
+                .filter(|&i| row[i] != 0)
+                .map(|i| (vec![i], row[i]))
+                .collect(),
+            _ => (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| vec![i, j]))
+                .map(|basis| {
+                    let det = self.det(&basis);
+                    (basis, det)
+                })
+                .filter(|&(_, det)| det != 0)
+                .collect(),
+        }
+    }
+
+    fn det(&self, basis: &[usize]) -> i128 {
+        match (&self.rows[..], basis) {
+            ([row], &[i]) => row[i],
+            ([xs, ys], &[i, j]) => xs[i] * ys[j] - ys[i] * xs[j],
+            _ => unreachable!(),
+        }
+    }
+
+    fn max_subdeterminant(&self) -> i128 {
+        let entries = self.rows.iter().flatten().map(|e| e.abs());
+        let dets = match &self.rows[..] {
+            [_, _] => self.bases().into_iter().map(|(_, d)| d.abs()).collect(),
+            _ => Vec::new(),
+        };
+
+        entries.chain(dets).max().unwrap_or(0).max(1)
+    }
+
+    // Basic presses times |det|, given the presses of every other button
+    fn basic_scaled(&self, basis: &[usize], det: i128, values: &[i128]) -> Vec<i128> {
+        let residual = self
+            .rows
+            .iter()
+            .zip(&self.rhs)
+            .map(|(row, &r)| {
+                r - (0..self.len())
+                    .filter(|k| !basis.contains(k))
+                    .map(|k| row[k] * values[k])
+                    .sum::<i128>()
+            })
+            .collect::<Vec<_>>();
+
+        self.solve_basis(basis, det, &residual)
+    }
+
+    // B^-1 * v times |det|, where B holds the basic columns
+    fn solve_basis(&self, basis: &[usize], det: i128, v: &[i128]) -> Vec<i128> {
+        let sign = det.signum();
+
+        match (&self.rows[..], basis) {
+            ([_], _) => vec![sign * v[0]],
+            ([xs, ys], &[i, j]) => vec![
+                sign * (ys[j] * v[0] - xs[j] * v[1]),
+                sign * (xs[i] * v[1] - ys[i] * v[0]),
+            ],
+            _ => unreachable!(),
+        }
+    }
+}
+
+struct Search<'a> {
+    system: &'a System,
+    vertex: &'a Vertex,
+    limit: Option<i128>,
+    free: Vec<usize>,
+    ranges: Vec<(i128, i128)>,
+    reduced: Vec<i128>,
+    rest_min: Vec<i128>,
+    best: Option<(i128, Vec<i128>)>,
+}
+
+impl Search<'_> {
+    // `delta` is the cost change (times |det|) of the non-basic presses chosen so far
+    fn visit(&mut self, index: usize, values: &mut Vec<i128>, delta: i128) {
+        let det = self.vertex.det.abs();
+        let exceeds = |best: &Option<(i128, Vec<i128>)>, scaled: i128| {
+            best.as_ref().is_some_and(|(cost, _)| scaled >= cost * det)
+        };
+
+        if index == self.free.len() {
+            let basis = &self.vertex.basis;
+            let basic = self.system.basic_scaled(basis, self.vertex.det, values);
+
+            let valid = basic
+                .iter()
+                .all(|&x| x >= 0 && x % det == 0 && self.limit.is_none_or(|l| x / det <= l));
+
+            if valid {
+                for (&b, &x) in basis.iter().zip(&basic) {
+                    values[b] = x / det;
+                }
+
+                let cost = (self.vertex.scaled_cost + delta) / det;
+                if !exceeds(&self.best, cost * det) {
+                    self.best = Some((cost, values.clone()));
+                }
+            }
+
+            return;
+        }
+
+        let k = self.free[index];
+        let (lo, hi) = self.ranges[index];
+        let reduced = self.reduced[index];
+        let value = self.vertex.values[k];
+
+        // Walk in the direction of rising cost, so the first pruned value ends the loop
+        let presses: Box<dyn Iterator<Item = i128>> = if reduced >= 0 {
+            Box::new(lo..=hi)
+        } else {
+            Box::new((lo..=hi).rev())
+        };
+
+        for n in presses {
+            let delta = delta + reduced * (n - value);
+
+            if exceeds(
+                &self.best,
+                self.vertex.scaled_cost + delta + self.rest_min[index + 1],
+            ) {
+                break;
+            }
+
+            values[k] = n;
+            self.visit(index + 1, values, delta);
         }
 
-        0
+        values[k] = value;
     }
 }
 
-fn div_without_remainder(a: i64, b: i64) -> Option<i64> {
+fn solve_single(button: &Button, price: (i128, i128), max_presses: Option<i128>) -> Option<i128> {
+    let (dx, dy) = (button.offset.0 as i128, button.offset.1 as i128);
+    let (px, py) = price;
+
+    let n = match (dx, dy) {
+        (0, 0) => return (price == (0, 0)).then_some(0),
+        (0, _) => div_without_remainder(py, dy)?,
+        _ => div_without_remainder(px, dx)?,
+    };
+
+    (n >= 0 && n * dx == px && n * dy == py && max_presses.is_none_or(|m| n <= m)).then_some(n)
+}
+
+fn solve_pair(
+    a: &Button,
+    b: &Button,
+    price: (i128, i128),
+    max_presses: Option<i128>,
+) -> Option<(i128, i128)> {
+    let (ax, ay) = (a.offset.0 as i128, a.offset.1 as i128);
+    let (bx, by) = (b.offset.0 as i128, b.offset.1 as i128);
+    let (px, py) = price;
+
+    let det = ax * by - ay * bx;
+
+    if det != 0 {
+        let n = div_without_remainder(px * by - py * bx, det)?;
+        let m = div_without_remainder(py * ax - px * ay, det)?;
+
+        let in_range = |n: i128| n >= 0 && max_presses.is_none_or(|max| n <= max);
+
+        return (in_range(n) && in_range(m)).then_some((n, m));
+    }
+
+    // Collinear buttons: the prize has to lie on their common line
+    if ax * py - ay * px != 0 || bx * py - by * px != 0 {
+        return None;
+    }
+
+    // Every equation is then a multiple of a single one along a non-zero axis
+    let (u, v, w) = if ax != 0 || bx != 0 {
+        (ax, bx, px)
+    } else if px == 0 {
+        (ay, by, py)
+    } else {
+        return None;
+    };
+
+    solve_1d((u, a.cost as i128), (v, b.cost as i128), w, max_presses)
+}
+
+// Cheapest non-negative n, m with n * u + m * v == w, given (coefficient, cost) pairs
+fn solve_1d(
+    (u, cost_u): (i128, i128),
+    (v, cost_v): (i128, i128),
+    w: i128,
+    max_presses: Option<i128>,
+) -> Option<(i128, i128)> {
+    if u == 0 && v == 0 {
+        return (w == 0).then_some((0, 0));
+    }
+
+    let (g, x, y) = extended_gcd(u, v);
+    let scale = div_without_remainder(w, g)?;
+
+    // n = n0 + k * step_n, m = m0 + k * step_m for any integer k
+    let (n0, step_n) = (x * scale, v / g);
+    let (m0, step_m) = (y * scale, -u / g);
+
+    let mut range = (None, None);
+    for (start, step) in [(n0, step_n), (m0, step_m)] {
+        range = intersect(range, bounds(start, step, 0, max_presses)?)?;
+    }
+
+    let slope = step_n * cost_u + step_m * cost_v;
+    let k = match range {
+        (Some(min), _) if slope >= 0 => min,
+        (_, Some(max)) if slope <= 0 => max,
+        _ => return None,
+    };
+
+    Some((n0 + k * step_n, m0 + k * step_m))
+}
+
+type Range = (Option<i128>, Option<i128>);
+
+// Values of k for which min <= start + k * step <= max
+fn bounds(start: i128, step: i128, min: i128, max: Option<i128>) -> Option<Range> {
+    if step == 0 {
+        let in_range = start >= min && max.is_none_or(|max| start <= max);
+        return in_range.then_some((None, None));
+    }
+
+    let lower = div_ceil(min - start, step);
+    let upper = max.map(|max| div_floor(max - start, step));
+
+    Some(if step > 0 {
+        (Some(lower), upper)
+    } else {
+        (
+            max.map(|max| div_ceil(max - start, step)),
+            Some(div_floor(min - start, step)),
+        )
+    })
+}
+
+fn intersect(a: Range, b: Range) -> Option<Range> {
+    let min = a.0.into_iter().chain(b.0).max();
+    let max = a.1.into_iter().chain(b.1).min();
+
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => None,
+        range => Some(range),
+    }
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let (q, r) = (a / b, a % b);
+
+    if r != 0 && (r < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+// Returns (g, x, y) with a * x + b * y == g == gcd(a, b) and g > 0
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a.abs(), a.signum(), 0);
+    }
+
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+fn div_without_remainder(a: i128, b: i128) -> Option<i128> {
     if a % b == 0 {
         Some(a / b)
     } else {
@@ -48,14 +568,227 @@ fn div_without_remainder(a: i64, b: i64) -> Option<i64> {
 mod tests {
     use super::*;
 
+    fn machine(a: Point, b: Point, price: Point) -> Machine {
+        Machine::new(
+            vec![Button::new("A", a, None), Button::new("B", b, None)],
+            price,
+        )
+    }
+
+    // Checked independently by walking every residue class of C near both ends of its range
+    const EXPECTED_LARGE: ([u64; 3], u64) = ([81081081506, 108108104588, 2700], 351351351806);
+
+    fn solution(presses: &[u64], cost: u64) -> Option<Solution> {
+        Some(Solution {
+            presses: presses.to_vec(),
+            cost,
+        })
+    }
+
     #[test]
     fn test_solve() {
         let machine = Machine {
-            button_a: Point(94, 34),
-            button_b: Point(22, 67),
+            buttons: vec![
+                Button::new("A", Point(94, 34), None),
+                Button::new("B", Point(22, 67), None),
+            ],
             price: Point(8400, 5400),
         };
 
-        assert_eq!(machine.solve(), 280)
+        assert_eq!(machine.solve(), solution(&[80, 40], 280));
+
+        let machine = self::machine(Point(26, 66), Point(67, 21), Point(12748, 12176));
+        assert_eq!(machine.solve(), None);
+    }
+
+    #[test]
+    fn test_solve_limited() {
+        let machine = machine(Point(94, 34), Point(22, 67), Point(8400, 5400));
+
+        assert!(machine.solve_limited(Some(100)).is_some());
+        assert_eq!(machine.solve_limited(Some(79)), None);
+    }
+
+    #[test]
+    fn test_solve_collinear() {
+        let solve = |a, b, p, max_presses| machine(a, b, p).solve_limited(max_presses);
+
+        assert_eq!(
+            solve(Point(1, 1), Point(2, 2), Point(10, 10), None),
+            solution(&[0, 5], 5)
+        );
+        assert_eq!(
+            solve(Point(4, 4), Point(1, 1), Point(8, 8), None),
+            solution(&[2, 0], 6)
+        );
+        assert_eq!(
+            solve(Point(1, 1), Point(2, 2), Point(10, 10), Some(4)),
+            solution(&[2, 4], 10)
+        );
+        assert_eq!(solve(Point(2, 2), Point(4, 4), Point(5, 5), None), None);
+        assert_eq!(solve(Point(1, 1), Point(2, 2), Point(1, 2), None), None);
+        assert_eq!(
+            solve(Point(1, 1), Point(2, 2), Point(10, 10), Some(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_solve_degenerate() {
+        let solve = |a, b, p| machine(a, b, p).solve();
+
+        assert_eq!(
+            solve(Point(0, 0), Point(0, 0), Point(0, 0)),
+            solution(&[0, 0], 0)
+        );
+        assert_eq!(solve(Point(0, 0), Point(0, 0), Point(1, 0)), None);
+        assert_eq!(
+            solve(Point(0, 0), Point(0, 3), Point(0, 9)),
+            solution(&[0, 3], 3)
+        );
+        assert_eq!(
+            solve(Point(2, 0), Point(3, 0), Point(7, 0)),
+            solution(&[2, 1], 7)
+        );
+    }
+
+    #[test]
+    fn test_solve_buttons() {
+        let mut machine = Machine::new(
+            vec![
+                Button::new("A", Point(1, 0), None),
+                Button::new("B", Point(0, 1), None),
+                Button::new("C", Point(1, 1), None),
+            ],
+            Point(5, 7),
+        );
+
+        assert_eq!(machine.solve(), solution(&[0, 2, 5], 7));
+        assert_eq!(machine.solve_limited(Some(4)), solution(&[1, 3, 4], 10));
+
+        machine.set_cost("C", 5);
+        assert_eq!(machine.solve(), solution(&[5, 7, 0], 22));
+
+        let single = Machine::new(vec![Button::new("A", Point(2, 3), None)], Point(8, 12));
+        assert_eq!(single.solve(), solution(&[4], 12));
+        assert_eq!(
+            Machine::new(Vec::new(), Point(0, 0)).solve(),
+            solution(&[], 0)
+        );
+        assert_eq!(Machine::new(Vec::new(), Point(1, 0)).solve(), None);
+    }
+
+    #[test]
+    fn test_solve_backwards_button() {
+        let machine = Machine::new(
+            vec![
+                Button::new("C", Point(-1, 0), None),
+                Button::new("A", Point(2, 0), None),
+                Button::new("B", Point(0, 1), None),
+            ],
+            Point(1, 0),
+        );
+
+        assert_eq!(machine.solve(), solution(&[1, 1, 0], 4));
+    }
+
+    #[test]
+    fn test_solve_buttons_limited() {
+        let offsets = [
+            [Point(3, 1), Point(1, 2), Point(2, 2)],
+            [Point(-1, 2), Point(4, 1), Point(1, -1)],
+            [Point(2, 4), Point(1, 2), Point(3, 6)],
+            [Point(0, 1), Point(5, 0), Point(-2, 3)],
+        ];
+        let prices = [Point(11, 9), Point(7, 8), Point(9, 18), Point(6, 14)];
+
+        for (offsets, price) in offsets.into_iter().zip(prices) {
+            let machine = Machine::new(
+                ["A", "B", "C"]
+                    .into_iter()
+                    .zip(offsets)
+                    .map(|(name, offset)| Button::new(name, offset, None))
+                    .collect(),
+                price,
+            );
+
+            for limit in [2, 4, 12] {
+                let brute = (0..=limit)
+                    .flat_map(|a| {
+                        (0..=limit).flat_map(move |b| (0..=limit).map(move |c| [a, b, c]))
+                    })
+                    .filter(|presses| {
+                        presses
+                            .iter()
+                            .zip(offsets)
+                            .fold(Point(0, 0), |sum, (&n, Point(x, y))| {
+                                sum + Point(n as i64 * x, n as i64 * y)
+                            })
+                            == price
+                    })
+                    .map(|presses| presses[0] * 3 + presses[1] + presses[2])
+                    .min();
+
+                assert_eq!(
+                    machine.solve_limited(Some(limit)).map(|s| s.cost),
+                    brute,
+                    "{offsets:?} {price:?} {limit}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_buttons_collinear() {
+        let mut machine = Machine::new(
+            vec![
+                Button::new("A", Point(3, 3), None),
+                Button::new("B", Point(5, 5), None),
+                Button::new("C", Point(-2, -2), None),
+            ],
+            Point(1, 1),
+        );
+
+        assert_eq!(machine.solve(), solution(&[0, 1, 2], 3));
+
+        machine.offset_price(Point(0, 1));
+        assert_eq!(machine.solve(), None);
+    }
+
+    #[test]
+    fn test_solve_buttons_large() {
+        let mut machine = Machine::new(
+            vec![
+                Button::new("A", Point(94, 34), None),
+                Button::new("B", Point(22, 67), None),
+                Button::new("C", Point(17, 84), None),
+            ],
+            Point(8400, 5400),
+        );
+        machine.offset_price(Point(10000000000000, 10000000000000));
+
+        assert_eq!(
+            machine.solve(),
+            solution(&EXPECTED_LARGE.0, EXPECTED_LARGE.1)
+        );
+        assert_eq!(machine.solve_limited(Some(100)), None);
+    }
+
+    #[test]
+    fn test_offset_price() {
+        let mut machine = machine(Point(94, 34), Point(22, 67), Point(8400, 5400));
+        machine.offset_price(Point(-8400, -5400));
+
+        assert_eq!(machine.solve(), solution(&[0, 0], 0));
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        for (a, b) in [(240, 46), (-7, 3), (0, 5), (5, 0), (-4, -6)] {
+            let (g, x, y) = extended_gcd(a, b);
+
+            assert!(g > 0);
+            assert_eq!(a * x + b * y, g);
+        }
     }
 }
@@ -1,45 +1,89 @@
 mod machine;
-mod parse;
+pub mod parse;
 
 use std::error::Error;
 
-use crate::Puzzle;
+use crate::{util::Point, Puzzle};
+
+use machine::Machine;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+const MAX_PRESSES: u64 = 100;
+
+const PRICE_OFFSET: i64 = 10000000000000;
+
+// Button costs from the command line take precedence over those in the input
+fn parse_machines(
+    input: &str,
+    button_costs: &[(String, u64)],
+) -> Result<Vec<Machine>, Box<dyn Error>> {
+    let (_, mut machines) = parse::parse_input(input).map_err(|e| e.to_owned())?;
+
+    for machine in machines.iter_mut() {
+        for (name, cost) in button_costs {
+            machine.set_cost(name, *cost);
+        }
+    }
+
+    Ok(machines)
+}
+
+#[derive(Default)]
+pub struct Part1 {
+    button_costs: Vec<(String, u64)>,
+}
 
 impl Part1 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, machines) = parse::parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_button_costs(button_costs: Vec<(String, u64)>) -> Self {
+        Self { button_costs }
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+        let machines = parse_machines(input, &self.button_costs)?;
 
-        Ok(machines.iter().map(|m| m.solve()).sum())
+        Ok(machines
+            .iter()
+            .filter_map(|m| m.solve_limited(Some(MAX_PRESSES)))
+            .map(|solution| solution.cost)
+            .sum())
     }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
     }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    button_costs: Vec<(String, u64)>,
+}
 
 impl Part2 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, mut machines) = parse::parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_button_costs(button_costs: Vec<(String, u64)>) -> Self {
+        Self { button_costs }
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+        let mut machines = parse_machines(input, &self.button_costs)?;
 
         for machine in machines.iter_mut() {
-            machine.increase_prices();
+            machine.offset_price(Point(PRICE_OFFSET, PRICE_OFFSET));
         }
 
-        Ok(machines.iter().map(|m| m.solve()).sum())
+        Ok(machines
+            .iter()
+            .filter_map(|m| m.solve())
+            .map(|solution| solution.cost)
+            .sum())
     }
 }
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
     }
 }
 
@@ -51,11 +95,20 @@ mod tests {
 
     #[test]
     fn part1() {
-        assert_eq!(Part1::solve_input(TEST_INPUT).unwrap(), 480);
+        assert_eq!(Part1::default().solve_input(TEST_INPUT).unwrap(), 480);
+    }
+
+    #[test]
+    fn part1_button_costs() {
+        let part = Part1::with_button_costs(vec![("A".to_string(), 1), ("B".to_string(), 1)]);
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 244);
     }
 
     #[test]
     fn part2() {
-        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 875318608908);
+        assert_eq!(
+            Part2::default().solve_input(TEST_INPUT).unwrap(),
+            875318608908
+        );
     }
 }
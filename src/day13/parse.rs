@@ -1,15 +1,15 @@
 use nom::{
     bytes::complete::tag,
-    character::complete::{alpha1, i64, newline, space0},
-    combinator::{map, map_res},
+    character::complete::{alpha1, i64, newline, space0, u64},
+    combinator::{all_consuming, map, map_res, opt},
     multi::{many1, separated_list0},
-    sequence::tuple,
+    sequence::{preceded, separated_pair, tuple},
     IResult,
 };
 
 use crate::util::Point;
 
-use super::machine::Machine;
+use super::machine::{Button, Machine};
 
 pub fn parse_input(input: &str) -> IResult<&str, Vec<Machine>> {
     separated_list0(many1(newline), parse_machine)(input)
@@ -19,22 +19,19 @@ fn parse_machine(input: &str) -> IResult<&str, Machine> {
     map_res(
         tuple((separated_list0(newline, parse_button), newline, parse_price)),
         |(buttons, _, price)| -> Result<_, String> {
-            let (_, button_a) = buttons
-                .iter()
-                .find(|(name, _)| *name == "A")
-                .ok_or("Button A not found")?;
-            let (_, button_b) = buttons
-                .iter()
-                .find(|(name, _)| *name == "B")
-                .ok_or("Button B not found")?;
-
-            Ok(Machine::new(*button_a, *button_b, price))
+            for (i, button) in buttons.iter().enumerate() {
+                if buttons[..i].iter().any(|other| other.name == button.name) {
+                    return Err(format!("Duplicate button {}", button.name));
+                }
+            }
+
+            Ok(Machine::new(buttons, price))
         },
     )(input)
 }
 
-fn parse_button(input: &str) -> IResult<&str, (&str, Point)> {
-    // "Button A: X+94, Y+34"
+fn parse_button(input: &str) -> IResult<&str, Button> {
+    // "Button A: X+94, Y+34", optionally followed by ", Cost=3"
     map(
         tuple((
             tag("Button "),
@@ -47,11 +44,23 @@ fn parse_button(input: &str) -> IResult<&str, (&str, Point)> {
             space0,
             tag("Y"),
             i64,
+            opt(preceded(tuple((tag(","), space0, tag("Cost="))), u64)),
         )),
-        |(_, name, _, _, _, x, _, _, _, y)| (name, Point(x, y)),
+        |(_, name, _, _, _, x, _, _, _, y, cost)| Button::new(name, Point(x, y), cost),
     )(input)
 }
 
+pub fn parse_button_cost(input: &str) -> Result<(String, u64), String> {
+    // "A=3", as given on the command line
+    all_consuming(separated_pair(
+        alpha1::<_, nom::error::Error<_>>,
+        tag("="),
+        u64,
+    ))(input)
+    .map(|(_, (name, cost))| (name.to_string(), cost))
+    .map_err(|_| format!("Invalid button cost '{input}', expected NAME=COST"))
+}
+
 fn parse_price(input: &str) -> IResult<&str, Point> {
     // "Prize: X=8400, Y=5400"
     map(
@@ -82,15 +91,33 @@ mod tests {
         assert!(res.is_ok());
 
         let (_, machine) = res.unwrap();
+        assert_eq!(machine, machine_ab());
+    }
+
+    #[test]
+    fn test_parse_machine_buttons() {
+        let (_, machine) = parse_machine(
+            "Button A: X+1, Y+0\nButton B: X+0, Y+1\nButton C: X+1, Y+1, Cost=2\nPrize: X=5, Y=7",
+        )
+        .unwrap();
+
         assert_eq!(
             machine,
-            Machine::new(Point(94, 34), Point(22, 67), Point(8400, 5400))
+            Machine::new(
+                vec![
+                    Button::new("A", Point(1, 0), None),
+                    Button::new("B", Point(0, 1), None),
+                    Button::new("C", Point(1, 1), Some(2)),
+                ],
+                Point(5, 7)
+            )
         );
     }
 
     #[test]
     fn test_parse_machine_error() {
-        let res = parse_machine("Button A: X+94, Y+34\nPrize: X=8400, Y=5400");
+        let res =
+            parse_machine("Button A: X+94, Y+34\nButton A: X+22, Y+67\nPrize: X=8400, Y=5400");
         assert!(res.is_err());
     }
 
@@ -99,9 +126,21 @@ mod tests {
         let res = parse_button("Button A: X+94, Y+34");
         assert!(res.is_ok());
 
-        let (_, (name, point)) = res.unwrap();
-        assert_eq!(name, "A");
-        assert_eq!(point, Point(94, 34));
+        let (_, button) = res.unwrap();
+        assert_eq!(button.name, "A");
+        assert_eq!(button.offset, Point(94, 34));
+        assert_eq!(button.cost, 3);
+
+        let (_, button) = parse_button("Button Turbo: X+1, Y-2, Cost=7").unwrap();
+        assert_eq!(button, Button::new("Turbo", Point(1, -2), Some(7)));
+    }
+
+    #[test]
+    fn test_parse_button_cost() {
+        assert_eq!(parse_button_cost("A=5"), Ok(("A".to_string(), 5)));
+        assert!(parse_button_cost("A=").is_err());
+        assert!(parse_button_cost("A=5x").is_err());
+        assert!(parse_button_cost("5").is_err());
     }
 
     #[test]
@@ -119,9 +158,16 @@ mod tests {
         assert!(res.is_ok());
 
         let (_, machines) = res.unwrap();
-        assert_eq!(
-            machines[0],
-            Machine::new(Point(94, 34), Point(22, 67), Point(8400, 5400))
-        );
+        assert_eq!(machines[0], machine_ab());
+    }
+
+    fn machine_ab() -> Machine {
+        Machine::new(
+            vec![
+                Button::new("A", Point(94, 34), None),
+                Button::new("B", Point(22, 67), None),
+            ],
+            Point(8400, 5400),
+        )
     }
 }
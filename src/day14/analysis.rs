@@ -0,0 +1,407 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+};
+
+use crate::util::{Direction, Point};
+
+use super::robot::Robot;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Metric {
+    // Sum of the x and y variances, lower when robots cluster
+    #[default]
+    Variance,
+    // Sum of the Shannon entropies of the x and y histograms, lower when robots line up
+    Entropy,
+    // Size of the largest 4-connected group of occupied cells
+    LargestArea,
+}
+
+impl Metric {
+    fn lower_is_better(&self) -> bool {
+        !matches!(self, Metric::LargestArea)
+    }
+
+    // Score of one axis, for metrics where the axes can be scored independently
+    fn axis_score(&self, values: &[i64]) -> Option<f64> {
+        match self {
+            Metric::Variance => Some(variance(values)),
+            Metric::Entropy => Some(entropy(values)),
+            Metric::LargestArea => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Frame {
+    pub round: u64,
+    pub score: f64,
+}
+
+pub struct Analysis {
+    robots: Vec<Robot>,
+    size: Point,
+}
+
+impl Analysis {
+    pub fn new(robots: Vec<Robot>, size: Point) -> Result<Self, Box<dyn Error>> {
+        if size.0 < 1 || size.1 < 1 {
+            return Err(format!("Invalid grid size {}x{}", size.0, size.1).into());
+        }
+
+        Ok(Self { robots, size })
+    }
+
+    // Every robot is back where it started after this many rounds
+    pub fn period(&self) -> u64 {
+        let (width, height) = (self.size.0 as u64, self.size.1 as u64);
+
+        width / gcd(width, height) * height
+    }
+
+    pub fn positions_at(&self, round: u64) -> Vec<Point> {
+        self.robots
+            .iter()
+            .map(|robot| robot.position_at(round, self.size))
+            .collect()
+    }
+
+    pub fn score(&self, round: u64, metric: Metric) -> f64 {
+        let positions = self.positions_at(round);
+
+        match metric {
+            Metric::LargestArea => largest_area(&positions) as f64,
+            _ => {
+                let (xs, ys) = split_axes(&positions);
+
+                metric.axis_score(&xs).unwrap_or_default()
+                    + metric.axis_score(&ys).unwrap_or_default()
+            }
+        }
+    }
+
+    // The `count` most picture-like rounds within one full period, best first
+    pub fn best_frames(&self, metric: Metric, count: usize) -> Vec<Frame> {
+        let mut frames = (0..self.period())
+            .map(|round| Frame {
+                round,
+                score: self.score(round, metric),
+            })
+            .collect::<Vec<_>>();
+
+        frames.sort_by(|a, b| {
+            let order = a.score.total_cmp(&b.score);
+
+            if metric.lower_is_better() {
+                order
+            } else {
+                order.reverse()
+            }
+            .then(a.round.cmp(&b.round))
+        });
+        frames.truncate(count);
+
+        frames
+    }
+
+    // Finds the best round per axis and combines them with the CRT,
+    // falling back to a full scan for metrics that cannot be split by axis
+    pub fn find_picture(&self, metric: Metric) -> Result<u64, Box<dyn Error>> {
+        if metric.axis_score(&[]).is_none() {
+            return self
+                .best_frames(metric, 1)
+                .first()
+                .map(|frame| frame.round)
+                .ok_or_else(|| "No frames to analyse".into());
+        }
+
+        let (width, height) = (self.size.0 as u64, self.size.1 as u64);
+
+        let best_round = |period: u64, axis: fn(&Point) -> i64| {
+            (0..period)
+                .map(|round| {
+                    let values = self
+                        .positions_at(round)
+                        .iter()
+                        .map(axis)
+                        .collect::<Vec<_>>();
+
+                    (metric.axis_score(&values).unwrap_or_default(), round)
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+                .map(|(_, round)| round)
+        };
+
+        let tx = best_round(width, |p| p.0).ok_or("Grid has no width")?;
+        let ty = best_round(height, |p| p.1).ok_or("Grid has no height")?;
+
+        crt((tx, width), (ty, height)).ok_or_else(|| {
+            format!("Rounds {tx} mod {width} and {ty} mod {height} never coincide").into()
+        })
+    }
+
+    pub fn render(&self, round: u64) -> String {
+        let counts = self.counts_at(round);
+
+        (0..self.size.1)
+            .map(|y| {
+                (0..self.size.0)
+                    .map(|x| match counts.get(&Point(x, y)) {
+                        None => '.',
+                        Some(&count) if count < 10 => char::from_digit(count as u32, 10).unwrap(),
+                        Some(_) => '#',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Plain (P1) PBM image with one pixel per cell, black where any robot stands
+    pub fn to_pbm(&self, round: u64) -> String {
+        let counts = self.counts_at(round);
+
+        let rows = (0..self.size.1).map(|y| {
+            (0..self.size.0)
+                .map(|x| {
+                    if counts.contains_key(&Point(x, y)) {
+                        "1"
+                    } else {
+                        "0"
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+
+        ["P1".to_string(), format!("{} {}", self.size.0, self.size.1)]
+            .into_iter()
+            .chain(rows)
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n"
+    }
+
+    fn counts_at(&self, round: u64) -> HashMap<Point, usize> {
+        let mut counts = HashMap::new();
+
+        for position in self.positions_at(round) {
+            *counts.entry(position).or_insert(0) += 1;
+        }
+
+        counts
+    }
+}
+
+fn split_axes(positions: &[Point]) -> (Vec<i64>, Vec<i64>) {
+    positions.iter().map(|&Point(x, y)| (x, y)).unzip()
+}
+
+fn variance(values: &[i64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<i64>() as f64 / n;
+
+    values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n
+}
+
+fn entropy(values: &[i64]) -> f64 {
+    let mut counts = HashMap::new();
+
+    for &value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+
+    let n = values.len() as f64;
+
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn largest_area(positions: &[Point]) -> usize {
+    let positions = positions.iter().copied().collect::<HashSet<_>>();
+
+    let mut processed = HashSet::new();
+    let mut max_area = 0;
+
+    for &position in positions.iter() {
+        if !processed.insert(position) {
+            continue;
+        }
+
+        let mut area = 0;
+        let mut queue = VecDeque::from([position]);
+
+        while let Some(position) = queue.pop_front() {
+            area += 1;
+
+            for direction in Direction::all() {
+                let neighbor = position + direction;
+
+                if positions.contains(&neighbor) && processed.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        max_area = max_area.max(area);
+    }
+
+    max_area
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Smallest t with t == a (mod m) and t == b (mod n), if there is one
+fn crt((a, m): (u64, u64), (b, n): (u64, u64)) -> Option<u64> {
+    let g = gcd(m, n);
+
+    if a % g != b % g {
+        return None;
+    }
+
+    let lcm = m / g * n;
+
+    // Step through a, a + m, a + 2m, ... which visits every residue mod n within n / g steps
+    (0..n / g)
+        .map(|k| a % m + k * m)
+        .find(|t| t % n == b % n)
+        .map(|t| t % lcm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Robots that all meet at `target` after `round` rounds
+    fn converging(size: Point, target: Point, round: i64) -> Vec<Robot> {
+        [
+            Point(1, 2),
+            Point(-2, 3),
+            Point(3, -1),
+            Point(2, 5),
+            Point(-1, -4),
+        ]
+        .into_iter()
+        .map(|velocity| {
+            let start = Point(target.0 - velocity.0 * round, target.1 - velocity.1 * round);
+            Robot::new(start.wrap(size), velocity)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt((2, 3), (3, 5)), Some(8));
+        assert_eq!(crt((0, 101), (0, 103)), Some(0));
+        assert_eq!(crt((1, 4), (3, 6)), Some(9));
+        assert_eq!(crt((1, 4), (2, 6)), None);
+    }
+
+    #[test]
+    fn test_period() {
+        let analysis = Analysis::new(Vec::new(), Point(11, 7)).unwrap();
+        assert_eq!(analysis.period(), 77);
+
+        let analysis = Analysis::new(Vec::new(), Point(4, 6)).unwrap();
+        assert_eq!(analysis.period(), 12);
+
+        assert!(Analysis::new(Vec::new(), Point(0, 6)).is_err());
+        assert!(Analysis::new(Vec::new(), Point(4, -1)).is_err());
+    }
+
+    #[test]
+    fn test_positions_at() {
+        let size = Point(11, 7);
+        let robot = Robot::new(Point(2, 4), Point(2, -3));
+        let analysis = Analysis::new(vec![robot], size).unwrap();
+
+        assert_eq!(analysis.positions_at(0), vec![Point(2, 4)]);
+        assert_eq!(analysis.positions_at(1), vec![Point(4, 1)]);
+        assert_eq!(analysis.positions_at(5), vec![Point(1, 3)]);
+
+        assert_eq!(
+            analysis.positions_at(77 * 1000 + 5),
+            analysis.positions_at(5)
+        );
+    }
+
+    #[test]
+    fn test_find_picture() {
+        let size = Point(5, 7);
+        let analysis = Analysis::new(converging(size, Point(2, 3), 23), size).unwrap();
+
+        assert_eq!(analysis.find_picture(Metric::Variance).unwrap(), 23);
+        assert_eq!(analysis.find_picture(Metric::Entropy).unwrap(), 23);
+        assert_eq!(analysis.score(23, Metric::Variance), 0.0);
+        assert_eq!(analysis.score(23, Metric::LargestArea), 1.0);
+    }
+
+    #[test]
+    fn test_best_frames() {
+        let size = Point(5, 7);
+        let analysis = Analysis::new(converging(size, Point(2, 3), 23), size).unwrap();
+
+        let frames = analysis.best_frames(Metric::Entropy, 3);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames[0],
+            Frame {
+                round: 23,
+                score: 0.0
+            }
+        );
+        assert!(frames[1].score >= frames[0].score);
+        assert!(frames[2].score >= frames[1].score);
+
+        let frames = analysis.best_frames(Metric::LargestArea, 100);
+        assert_eq!(frames.len(), 35);
+        assert!(frames[0].score >= frames[34].score);
+    }
+
+    #[test]
+    fn test_largest_area() {
+        let positions = [
+            Point(0, 0),
+            Point(1, 0),
+            Point(1, 1),
+            Point(3, 3),
+            Point(3, 4),
+        ];
+        assert_eq!(largest_area(&positions), 3);
+        assert_eq!(largest_area(&[]), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let robots = vec![
+            Robot::new(Point(0, 0), Point(1, 0)),
+            Robot::new(Point(1, 0), Point(0, 1)),
+            Robot::new(Point(1, 0), Point(0, 1)),
+        ];
+        let analysis = Analysis::new(robots, Point(3, 2)).unwrap();
+
+        assert_eq!(analysis.render(0), "12.\n...");
+        assert_eq!(analysis.render(1), ".1.\n.2.");
+        assert_eq!(analysis.to_pbm(0), "P1\n3 2\n1 1 0\n0 0 0\n");
+    }
+}
@@ -1,128 +1,105 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    error::Error,
-};
+use std::error::Error;
 
-use robot::Robot;
+use analysis::{Analysis, Metric};
+use quadrant::{CentreLine, Quadrants};
 
-use crate::{
-    util::{Direction, Point},
-    Puzzle,
-};
+use crate::{util::Point, Puzzle};
 
+pub mod analysis;
 mod parse;
+pub mod quadrant;
 mod robot;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+const SIZE: Point = Point(101, 103);
+
+// Best rounds listed in the report
+const REPORT_FRAMES: usize = 3;
+
+#[derive(Default)]
+pub struct Part1 {
+    centre_line: CentreLine,
+}
 
 impl Part1 {
-    fn solve_input(input: &str, size: Point) -> Result<u64, Box<dyn Error>> {
-        let (_, mut robots) = parse::parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_centre_line(centre_line: CentreLine) -> Self {
+        Self { centre_line }
+    }
+
+    fn solve_input(&self, input: &str, size: Point) -> Result<u64, Box<dyn Error>> {
+        let (_, robots) = parse::parse_input(input).map_err(|e| e.to_owned())?;
 
         let round_count = 100;
 
-        for robot in robots.iter_mut() {
-            for _ in 0..round_count {
-                robot.move_forward(size);
-            }
-        }
-
-        let lt = robots
-            .iter()
-            .filter(|r| r.position.0 < size.0 / 2 && r.position.1 < size.1 / 2)
-            .count();
-
-        let rt = robots
-            .iter()
-            .filter(|r| r.position.0 > size.0 / 2 && r.position.1 < size.1 / 2)
-            .count();
-
-        let lb = robots
-            .iter()
-            .filter(|r| r.position.0 < size.0 / 2 && r.position.1 > size.1 / 2)
-            .count();
-
-        let rb = robots
-            .iter()
-            .filter(|r| r.position.0 > size.0 / 2 && r.position.1 > size.1 / 2)
-            .count();
-
-        Ok((lt * rt * lb * rb) as u64)
+        let positions = robots.iter().map(|r| r.position_at(round_count, size));
+
+        Ok(Quadrants::count(positions, size, self.centre_line).safety_factor())
     }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Part1::solve_input(INPUT, Point(101, 103)).map(|res| res.to_string())
+        self.solve_input(INPUT, SIZE).map(|res| res.to_string())
     }
 }
 
-pub struct Part2;
-
-impl Puzzle for Part2 {
-    fn solve(&self) -> Result<String, Box<dyn Error>> {
-        let (_, mut robots) = parse::parse_input(INPUT).map_err(|e| e.to_owned())?;
-
-        let size = Point(101, 103);
-
-        for round in 0.. {
-            let area = biggest_area(&robots);
+#[derive(Default)]
+pub struct Part2 {
+    metric: Metric,
+}
 
-            if area >= 100 {
-                return Ok(round.to_string());
-            }
+impl Part2 {
+    pub fn with_metric(metric: Metric) -> Self {
+        Self { metric }
+    }
 
-            for robot in robots.iter_mut() {
-                robot.move_forward(size);
-            }
-        }
+    fn analysis(input: &str, size: Point) -> Result<Analysis, Box<dyn Error>> {
+        let (_, robots) = parse::parse_input(input).map_err(|e| e.to_owned())?;
 
-        panic!("Not found");
+        Analysis::new(robots, size)
     }
-}
-
-fn biggest_area(robots: &[Robot]) -> u64 {
-    let postitions = robots.iter().map(|r| r.position).collect::<HashSet<_>>();
 
-    let mut processed_positions = HashSet::new();
+    fn solve_input(&self, input: &str, size: Point) -> Result<u64, Box<dyn Error>> {
+        Self::analysis(input, size)?.find_picture(self.metric)
+    }
 
-    let mut max_count = 0;
+    // Best scoring rounds, then the grid at the round the picture was found
+    fn report_input(&self, input: &str, size: Point) -> Result<String, Box<dyn Error>> {
+        let analysis = Self::analysis(input, size)?;
 
-    for &position in postitions.iter() {
-        if processed_positions.contains(&position) {
-            continue;
-        }
+        let frames = analysis
+            .best_frames(self.metric, REPORT_FRAMES)
+            .into_iter()
+            .map(|frame| format!("round {}: {:.3}", frame.round, frame.score))
+            .collect::<Vec<_>>()
+            .join("\n");
 
-        let mut count = 0;
-        let mut queue = VecDeque::new();
+        let round = analysis.find_picture(self.metric)?;
 
-        processed_positions.insert(position);
-        queue.push_back(position);
+        Ok(format!("{frames}\n{}", analysis.render(round)))
+    }
 
-        while let Some(position) = queue.pop_front() {
-            for direction in Direction::all() {
-                let neighbor_pos = position + direction;
+    fn image_input(&self, input: &str, size: Point) -> Result<String, Box<dyn Error>> {
+        let analysis = Self::analysis(input, size)?;
 
-                if !postitions.contains(&neighbor_pos) {
-                    continue;
-                }
+        Ok(analysis.to_pbm(analysis.find_picture(self.metric)?))
+    }
+}
 
-                if !processed_positions.contains(&neighbor_pos) {
-                    processed_positions.insert(neighbor_pos);
-                    queue.push_back(neighbor_pos);
-                    count += 1;
-                }
-            }
-        }
+impl Puzzle for Part2 {
+    fn solve(&self) -> Result<String, Box<dyn Error>> {
+        self.solve_input(INPUT, SIZE).map(|round| round.to_string())
+    }
 
-        if count > max_count {
-            max_count = count;
-        }
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT, SIZE).map(Some)
     }
 
-    max_count
+    // The picture as a PBM image
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.image_input(INPUT, SIZE).map(Some)
+    }
 }
 
 #[cfg(test)]
@@ -133,10 +110,28 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let res = Part1::solve_input(TEST_INPUT, Point(7, 11));
+        let res = Part1::default().solve_input(TEST_INPUT, Point(7, 11));
         assert!(res.is_ok());
 
         let result = res.unwrap();
         assert_eq!(result, 12);
+
+        let part = Part1::with_centre_line(CentreLine::Before);
+        assert_eq!(part.solve_input(TEST_INPUT, Point(7, 11)).unwrap(), 45);
+    }
+
+    #[test]
+    fn test_part2() {
+        let part = Part2::with_metric(Metric::LargestArea);
+
+        let report = part.report_input(TEST_INPUT, Point(11, 7)).unwrap();
+        assert_eq!(report.lines().count(), REPORT_FRAMES + 7);
+
+        let image = part.image_input(TEST_INPUT, Point(11, 7)).unwrap();
+        assert!(image.starts_with("P1\n11 7\n"));
+
+        assert!(Part2::default()
+            .solve_input(TEST_INPUT, Point(0, 7))
+            .is_err());
     }
 }
@@ -0,0 +1,127 @@
+use crate::util::Point;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum CentreLine {
+    // Robots on a middle row or column of an odd-sized grid are not counted
+    #[default]
+    Exclude,
+    // They count towards the left or top half
+    Before,
+    // They count towards the right or bottom half
+    After,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Quadrants {
+    pub top_left: usize,
+    pub top_right: usize,
+    pub bottom_left: usize,
+    pub bottom_right: usize,
+}
+
+impl Quadrants {
+    pub fn count(
+        positions: impl IntoIterator<Item = Point>,
+        size: Point,
+        centre_line: CentreLine,
+    ) -> Self {
+        let mut quadrants = Self::default();
+
+        for Point(x, y) in positions {
+            let (Some(right), Some(bottom)) =
+                (half(x, size.0, centre_line), half(y, size.1, centre_line))
+            else {
+                continue;
+            };
+
+            let count = match (right, bottom) {
+                (false, false) => &mut quadrants.top_left,
+                (true, false) => &mut quadrants.top_right,
+                (false, true) => &mut quadrants.bottom_left,
+                (true, true) => &mut quadrants.bottom_right,
+            };
+
+            *count += 1;
+        }
+
+        quadrants
+    }
+
+    pub fn safety_factor(&self) -> u64 {
+        (self.top_left * self.top_right * self.bottom_left * self.bottom_right) as u64
+    }
+}
+
+// Whether a coordinate lies in the upper half of an axis of length `len`, if it is counted at all
+fn half(value: i64, len: i64, centre_line: CentreLine) -> Option<bool> {
+    let middle = len / 2;
+
+    if len % 2 == 0 || value != middle {
+        return Some(value >= middle);
+    }
+
+    match centre_line {
+        CentreLine::Exclude => None,
+        CentreLine::Before => Some(false),
+        CentreLine::After => Some(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        let positions = [
+            Point(0, 0),
+            Point(2, 0),
+            Point(1, 1),
+            Point(0, 2),
+            Point(2, 2),
+            Point(1, 0),
+        ];
+
+        let quadrants = Quadrants::count(positions, Point(3, 3), CentreLine::Exclude);
+        assert_eq!(
+            quadrants,
+            Quadrants {
+                top_left: 1,
+                top_right: 1,
+                bottom_left: 1,
+                bottom_right: 1,
+            }
+        );
+        assert_eq!(quadrants.safety_factor(), 1);
+
+        let quadrants = Quadrants::count(positions, Point(3, 3), CentreLine::Before);
+        assert_eq!(
+            quadrants,
+            Quadrants {
+                top_left: 3,
+                top_right: 1,
+                bottom_left: 1,
+                bottom_right: 1,
+            }
+        );
+
+        let quadrants = Quadrants::count(positions, Point(3, 3), CentreLine::After);
+        assert_eq!(
+            quadrants,
+            Quadrants {
+                top_left: 1,
+                top_right: 2,
+                bottom_left: 1,
+                bottom_right: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_count_even() {
+        let positions = [Point(1, 1), Point(2, 1), Point(1, 2), Point(2, 2)];
+
+        let quadrants = Quadrants::count(positions, Point(4, 4), CentreLine::Exclude);
+        assert_eq!(quadrants.safety_factor(), 1);
+    }
+}
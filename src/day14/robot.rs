@@ -11,8 +11,14 @@ impl Robot {
         Robot { position, velocity }
     }
 
-    pub fn move_forward(&mut self, size: Point) {
-        self.position = (self.position + self.velocity).wrap(size);
+    // Position after `t` steps; each axis repeats with the grid size, so `t` is reduced first
+    pub fn position_at(&self, t: u64, size: Point) -> Point {
+        let steps = |len: i64| (t % len as u64) as i64;
+
+        let velocity = self.velocity.wrap(size);
+        let offset = Point(velocity.0 * steps(size.0), velocity.1 * steps(size.1)).wrap(size);
+
+        (self.position.wrap(size) + offset).wrap(size)
     }
 }
 
@@ -33,12 +39,39 @@ mod tests {
 
     #[test]
     fn test_move() {
-        let mut robot = Robot {
+        let robot = Robot {
             position: Point(5, 6),
             velocity: Point(8, -7),
         };
-        robot.move_forward(Point(10, 10));
 
-        assert_eq!(robot.position, Point(3, 9));
+        assert_eq!(robot.position_at(1, Point(10, 10)), Point(3, 9));
+    }
+
+    #[test]
+    fn test_position_at() {
+        let size = Point(11, 7);
+        let robot = Robot::new(Point(2, 4), Point(2, -3));
+
+        // The puzzle's example robot over its first five seconds
+        assert_eq!(
+            (0..6)
+                .map(|t| robot.position_at(t, size))
+                .collect::<Vec<_>>(),
+            vec![
+                Point(2, 4),
+                Point(4, 1),
+                Point(6, 5),
+                Point(8, 2),
+                Point(10, 6),
+                Point(1, 3)
+            ]
+        );
+        assert_eq!(robot.position_at(77 + 3, size), Point(8, 2));
+
+        let robot = Robot::new(Point(5, 6), Point(i64::MAX, i64::MIN));
+        assert_eq!(
+            robot.position_at(u64::MAX, size),
+            robot.position_at(u64::MAX % 77, size)
+        );
     }
 }
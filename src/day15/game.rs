@@ -1,118 +1,327 @@
-use std::collections::{HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+};
 
 use crate::util::{Direction, Point};
 
+use super::schedule::Schedule;
+
+#[derive(Debug, Clone)]
 pub struct Game {
     boxes: Vec<GameBox>,
+    // Index of the box occupying each cell
+    cells: HashMap<Point, usize>,
     wall_positions: HashSet<Point>,
-    pub robot_position: Point,
+    robots: Vec<Robot>,
+    robot_mode: RobotMode,
+    score: Score,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Robot {
+    pub position: Point,
     instructions: Vec<Direction>,
 }
 
-impl Game {
-    pub fn new(
-        boxes: Vec<GameBox>,
-        wall_positions: HashSet<Point>,
-        robot_position: Point,
-        instructions: Vec<Direction>,
-    ) -> Self {
+impl Robot {
+    pub fn new(position: Point, instructions: Vec<Direction>) -> Self {
         Self {
-            boxes,
-            wall_positions,
-            robot_position,
+            position,
             instructions,
         }
     }
+}
 
-    fn expand_point(point: Point) -> Point {
-        Point(point.0 * 2, point.1)
-    }
+// How a robot reacts when another robot moves into it
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum RobotMode {
+    #[default]
+    Blocking,
+    Pushable,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
 
-    fn expand_points(points: impl Iterator<Item = Point>) -> impl Iterator<Item = Point> {
-        points.flat_map(|p| {
-            let p = Self::expand_point(p);
-            [p, p + Direction::Right]
+impl Anchor {
+    // Corner of the box's bounding rectangle that distances are measured to
+    fn point(&self, game_box: &GameBox) -> Option<Point> {
+        let min_x = game_box.points.iter().map(|p| p.0).min()?;
+        let max_x = game_box.points.iter().map(|p| p.0).max()?;
+        let min_y = game_box.points.iter().map(|p| p.1).min()?;
+        let max_y = game_box.points.iter().map(|p| p.1).max()?;
+
+        Some(match self {
+            Anchor::TopLeft => Point(min_x, min_y),
+            Anchor::TopRight => Point(max_x, min_y),
+            Anchor::BottomLeft => Point(min_x, max_y),
+            Anchor::BottomRight => Point(max_x, max_y),
         })
     }
+}
 
-    pub fn expand(&self) -> Self {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Score {
+    pub anchor: Anchor,
+    pub column_weight: i64,
+    pub row_weight: i64,
+}
+
+impl Default for Score {
+    // GPS coordinates as defined by the puzzle
+    fn default() -> Self {
         Self {
-            boxes: self.boxes.iter().map(|b| b.expand()).collect(),
-            wall_positions: self
-                .wall_positions
-                .iter()
-                .flat_map(|&p| Self::expand_points(std::iter::once(p)))
-                .collect(),
-            robot_position: Self::expand_point(self.robot_position),
-            instructions: self.instructions.clone(),
+            anchor: Anchor::TopLeft,
+            column_weight: 1,
+            row_weight: 100,
         }
     }
+}
 
-    pub fn play(&mut self) -> u64 {
-        for &direction in &self.instructions.clone() {
-            let next_position = self.robot_position + direction;
+impl Game {
+    pub fn new(boxes: Vec<GameBox>, wall_positions: HashSet<Point>, robots: Vec<Robot>) -> Self {
+        let cells = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| b.points.iter().map(move |&p| (p, i)))
+            .collect();
 
-            if self.wall_positions.contains(&next_position) {
-                continue;
-            }
+        Self {
+            boxes,
+            cells,
+            wall_positions,
+            robots,
+            robot_mode: RobotMode::default(),
+            score: Score::default(),
+        }
+    }
 
-            if !self.push_boxes(next_position, direction) {
-                continue;
-            }
+    pub fn expand(&self) -> Result<Self, Box<dyn Error>> {
+        self.expand_by(2, 1)
+    }
 
-            self.robot_position += direction;
+    // Scales the map by a factor per axis; robots keep to the top-left cell of their tile
+    pub fn expand_by(&self, width: i64, height: i64) -> Result<Self, Box<dyn Error>> {
+        if width < 1 || height < 1 {
+            return Err(
+                format!("Invalid expansion {width}x{height}, factors must be positive").into(),
+            );
         }
 
-        self.boxes
+        let game = Self::new(
+            self.boxes
+                .iter()
+                .map(|b| Ok(GameBox::new(expand_points(&b.points, width, height)?)))
+                .collect::<Result<_, Box<dyn Error>>>()?,
+            expand_points(&self.wall_positions, width, height)?,
+            self.robots
+                .iter()
+                .map(|robot| {
+                    Ok(Robot {
+                        position: expand_point(robot.position, width, height)?,
+                        ..robot.clone()
+                    })
+                })
+                .collect::<Result<_, Box<dyn Error>>>()?,
+        );
+
+        Ok(game.with_score(self.score).with_robot_mode(self.robot_mode))
+    }
+
+    pub fn with_score(mut self, score: Score) -> Self {
+        self.score = score;
+        self
+    }
+
+    pub fn with_robot_mode(mut self, robot_mode: RobotMode) -> Self {
+        self.robot_mode = robot_mode;
+        self
+    }
+
+    pub fn robot_positions(&self) -> Vec<Point> {
+        self.robots.iter().map(|robot| robot.position).collect()
+    }
+
+    pub fn run(&mut self, schedule: &Schedule) -> Result<u64, Box<dyn Error>> {
+        for (robot, direction) in self.moves(schedule)? {
+            self.step_robot(robot, direction);
+        }
+
+        self.score()
+    }
+
+    fn moves(&self, schedule: &Schedule) -> Result<Vec<(usize, Direction)>, Box<dyn Error>> {
+        let stream_lens = self
+            .robots
             .iter()
-            .filter_map(|b| b.pos())
-            .map(|Point(x, y)| x + y * 100)
-            .sum::<i64>() as u64
+            .map(|robot| robot.instructions.len())
+            .collect::<Vec<_>>();
+
+        Ok(schedule
+            .order(&stream_lens)?
+            .into_iter()
+            .map(|(robot, index)| (robot, self.robots[robot].instructions[index]))
+            .collect())
     }
 
-    fn push_boxes(&mut self, point: Point, direction: Direction) -> bool {
-        let mut source_points = HashSet::new();
+    pub fn score(&self) -> Result<u64, Box<dyn Error>> {
+        let Score {
+            anchor,
+            column_weight,
+            row_weight,
+        } = self.score;
+
+        let total = self
+            .boxes
+            .iter()
+            .filter_map(|b| anchor.point(b))
+            .try_fold(0i64, |total, Point(x, y)| {
+                x.checked_mul(column_weight)?
+                    .checked_add(y.checked_mul(row_weight)?)?
+                    .checked_add(total)
+            })
+            .ok_or("Score overflows")?;
+
+        u64::try_from(total).map_err(|_| format!("Score {total} is negative").into())
+    }
+
+    // Moves a robot one cell, pushing whatever is ahead of it; returns false if it is blocked
+    pub fn step_robot(&mut self, robot: usize, direction: Direction) -> bool {
+        let mut boxes = HashSet::new();
+        let mut robots = HashSet::from([robot]);
 
         let mut queue = VecDeque::new();
-        queue.push_back(point);
+        queue.push_back(self.robots[robot].position + direction);
 
         while let Some(point) = queue.pop_front() {
-            if source_points.contains(&point) {
-                continue;
+            if self.wall_positions.contains(&point) {
+                return false;
             }
 
-            let box_points = self
-                .boxes
-                .iter()
-                .filter(|b| b.points.contains(&point))
-                .flat_map(|b| b.points.iter().copied());
-
-            for p in box_points {
-                source_points.insert(p);
+            if let Some(&i) = self.cells.get(&point) {
+                if boxes.insert(i) {
+                    queue.extend(self.boxes[i].points.iter().map(|&p| p + direction));
+                }
+            } else if let Some(other) = self.robots.iter().position(|r| r.position == point) {
+                if self.robot_mode == RobotMode::Blocking {
+                    return false;
+                }
 
-                let target_point = p + direction;
-                if !source_points.contains(&target_point) {
-                    queue.push_back(target_point);
+                if robots.insert(other) {
+                    queue.push_back(point + direction);
                 }
             }
         }
 
-        if source_points
-            .iter()
-            .map(|&p| p + direction)
-            .any(|p| self.wall_positions.contains(&p))
-        {
-            return false;
+        for &i in &boxes {
+            for p in &self.boxes[i].points {
+                self.cells.remove(p);
+            }
         }
 
-        for b in self.boxes.iter_mut() {
-            if !b.points.is_disjoint(&source_points) {
-                b.move_direction(direction);
+        for &i in &boxes {
+            self.boxes[i].move_direction(direction);
+
+            for &p in &self.boxes[i].points {
+                self.cells.insert(p, i);
             }
         }
 
+        for &i in &robots {
+            self.robots[i].position += direction;
+        }
+
         true
     }
+
+    // Every frame of the game, starting with the current state and then after each scheduled move
+    pub fn replay(
+        &self,
+        schedule: &Schedule,
+    ) -> Result<impl Iterator<Item = String>, Box<dyn Error>> {
+        let moves = self.moves(schedule)?;
+        let mut game = self.clone();
+
+        Ok(std::iter::once(self.render()).chain(moves.into_iter().map(
+            move |(robot, direction)| {
+                game.step_robot(robot, direction);
+                game.render()
+            },
+        )))
+    }
+
+    pub fn render(&self) -> String {
+        let points = self
+            .wall_positions
+            .iter()
+            .chain(self.cells.keys())
+            .chain(self.robots.iter().map(|robot| &robot.position));
+
+        let (min_x, max_x, min_y, max_y) = points.fold(
+            (i64::MAX, i64::MIN, i64::MAX, i64::MIN),
+            |(min_x, max_x, min_y, max_y), &Point(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+
+        (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| self.render_cell(Point(x, y)))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_cell(&self, point: Point) -> char {
+        if self.wall_positions.contains(&point) {
+            return '#';
+        }
+
+        if self.robots.iter().any(|robot| robot.position == point) {
+            return '@';
+        }
+
+        let Some(&i) = self.cells.get(&point) else {
+            return '.';
+        };
+
+        let same_box = |direction: Direction| self.cells.get(&(point + direction)) == Some(&i);
+
+        // Puzzle notation for boxes one row tall, line drawing once a box spans rows, so
+        // that any connected shape reads back unambiguously
+        match (
+            same_box(Direction::Left),
+            same_box(Direction::Right),
+            same_box(Direction::Up),
+            same_box(Direction::Down),
+        ) {
+            (false, false, false, false) => 'O',
+            (false, true, false, false) => '[',
+            (true, false, false, false) => ']',
+            (true, true, false, false) => '=',
+            (false, false, true, false) => '╵',
+            (false, false, false, true) => '╷',
+            (false, false, true, true) => '│',
+            (false, true, false, true) => '┌',
+            (true, false, false, true) => '┐',
+            (false, true, true, false) => '└',
+            (true, false, true, false) => '┘',
+            (true, true, false, true) => '┬',
+            (true, true, true, false) => '┴',
+            (false, true, true, true) => '├',
+            (true, false, true, true) => '┤',
+            (true, true, true, true) => '┼',
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -128,12 +337,254 @@ impl GameBox {
     fn move_direction(&mut self, direction: Direction) {
         self.points = self.points.iter().map(|&p| p + direction).collect();
     }
+}
+
+// Top-left cell of the tile a point expands to, checked so that the whole tile fits
+fn expand_point(point: Point, width: i64, height: i64) -> Result<Point, Box<dyn Error>> {
+    let expand = |value: i64, factor: i64| {
+        value
+            .checked_mul(factor)
+            .filter(|start| start.checked_add(factor - 1).is_some())
+    };
+
+    match (expand(point.0, width), expand(point.1, height)) {
+        (Some(x), Some(y)) => Ok(Point(x, y)),
+        _ => Err(format!("{point} does not fit once expanded by {width}x{height}").into()),
+    }
+}
+
+fn expand_points(
+    points: &HashSet<Point>,
+    width: i64,
+    height: i64,
+) -> Result<HashSet<Point>, Box<dyn Error>> {
+    let mut expanded = HashSet::new();
+
+    for &point in points {
+        let corner = expand_point(point, width, height)?;
+        expanded
+            .extend((0..height).flat_map(|dy| (0..width).map(move |dx| corner + Point(dx, dy))));
+    }
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse::parse_input, schedule::Timing};
+    use super::*;
+
+    const TEST_INPUT_2: &str = include_str!("test-input-2.txt");
+    const TEST_INPUT_3: &str = include_str!("test-input-3.txt");
+
+    #[test]
+    fn test_replay() {
+        let (_, game) = parse_input(TEST_INPUT_2).unwrap();
+        let frames = game
+            .replay(&Schedule::RoundRobin)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(frames.len(), 16);
+        assert_eq!(frames[0], frames[1]);
+        assert_eq!(
+            frames[2],
+            "########
+#.@O.O.#
+##..O..#
+#...O..#
+#.#.O..#
+#...O..#
+#......#
+########"
+        );
+        assert_eq!(
+            frames[15],
+            "########
+#....OO#
+##.....#
+#.....O#
+#.#O@..#
+#...O..#
+#...O..#
+########"
+        );
+    }
+
+    #[test]
+    fn test_render_expanded() {
+        let (_, game) = parse_input(TEST_INPUT_3).unwrap();
+        let game = game.expand().unwrap();
+
+        assert_eq!(
+            game.render(),
+            "##############
+##......##..##
+##..........##
+##....[][]@.##
+##....[]....##
+##..........##
+##############"
+        );
+        assert_eq!(
+            game.replay(&Schedule::RoundRobin).unwrap().last().unwrap(),
+            "##############
+##...[].##..##
+##...@.[]...##
+##....[]....##
+##..........##
+##..........##
+##############"
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        let (_, mut game) = parse_input(TEST_INPUT_2).unwrap();
+
+        assert!(!game.step_robot(0, Direction::Left));
+        assert!(game.step_robot(0, Direction::Up));
+        assert!(game.step_robot(0, Direction::Right));
+        assert_eq!(game.robot_positions(), vec![Point(3, 1)]);
+        assert_eq!(game.render_cell(Point(4, 1)), 'O');
+    }
+
+    #[test]
+    fn test_expand_by() {
+        let (_, game) = parse_input(TEST_INPUT_3).unwrap();
+        assert!(game.expand_by(0, 2).is_err());
+        assert!(game.expand_by(2, -1).is_err());
+        assert!(game.expand_by(i64::MAX / 2, 1).is_err());
+
+        let game = game.expand_by(3, 2).unwrap();
+
+        assert_eq!(
+            game.render().lines().take(8).collect::<Vec<_>>(),
+            vec![
+                "#####################",
+                "#####################",
+                "###.........###...###",
+                "###.........###...###",
+                "###...............###",
+                "###...............###",
+                "###......┌┬┐┌┬┐@..###",
+                "###......└┴┘└┴┘...###",
+            ]
+        );
+
+        let mut game = game;
+        assert!(game.step_robot(0, Direction::Left));
+        assert_eq!(game.render_cell(Point(8, 7)), '└');
+        assert_eq!(game.score().unwrap(), (8 + 600) + (11 + 600) + (9 + 800));
+    }
+
+    #[test]
+    fn test_render_shapes() {
+        // An L shape next to a box that would otherwise continue its bottom row
+        let game = Game::new(
+            vec![
+                GameBox::new(HashSet::from([Point(1, 0), Point(1, 1), Point(2, 1)])),
+                GameBox::new(HashSet::from([Point(3, 1), Point(4, 1)])),
+                GameBox::new(HashSet::from([Point(3, 0)])),
+            ],
+            HashSet::new(),
+            vec![Robot::new(Point(0, 0), Vec::new())],
+        );
+
+        assert_eq!(game.render(), "@╷.O.\n.└][]");
+    }
+
+    #[test]
+    fn test_score() {
+        let game = Game::new(
+            vec![GameBox::new(HashSet::from([
+                Point(2, 1),
+                Point(3, 1),
+                Point(3, 2),
+            ]))],
+            HashSet::new(),
+            vec![Robot::new(Point(0, 0), Vec::new())],
+        );
+
+        assert_eq!(game.score().unwrap(), 102);
 
-    fn pos(&self) -> Option<Point> {
-        self.points.iter().min_by_key(|Point(x, y)| (x, y)).copied()
+        let game = game.with_score(Score {
+            anchor: Anchor::BottomRight,
+            column_weight: 1,
+            row_weight: 10,
+        });
+
+        assert_eq!(game.score().unwrap(), 23);
+
+        let game = game.with_score(Score {
+            anchor: Anchor::TopLeft,
+            column_weight: -1,
+            row_weight: 1,
+        });
+        assert!(game.score().is_err());
+
+        let game = game.with_score(Score {
+            anchor: Anchor::TopLeft,
+            column_weight: i64::MAX,
+            row_weight: 1,
+        });
+        assert!(game.score().is_err());
     }
 
-    fn expand(&self) -> Self {
-        Self::new(Game::expand_points(self.points.iter().copied()).collect())
+    #[test]
+    fn test_multiple_robots() {
+        let robots = |streams: [&[Direction]; 2]| {
+            vec![
+                Robot::new(Point(1, 1), streams[0].to_vec()),
+                Robot::new(Point(3, 1), streams[1].to_vec()),
+            ]
+        };
+        let walls = (0..7)
+            .flat_map(|x| [Point(x, 0), Point(x, 2)])
+            .chain([Point(0, 1), Point(6, 1)])
+            .collect::<HashSet<_>>();
+        let boxes = vec![GameBox::new(HashSet::from([Point(2, 1)]))];
+
+        let mut game = Game::new(
+            boxes.clone(),
+            walls.clone(),
+            robots([&[Direction::Right], &[Direction::Left]]),
+        );
+        assert_eq!(game.render().lines().nth(1), Some("#@O@..#"));
+        assert_eq!(game.run(&Schedule::RoundRobin).unwrap(), 102);
+        assert_eq!(game.robot_positions(), vec![Point(1, 1), Point(3, 1)]);
+
+        let mut game = Game::new(
+            boxes.clone(),
+            walls.clone(),
+            robots([&[Direction::Right, Direction::Right], &[]]),
+        )
+        .with_robot_mode(RobotMode::Pushable);
+        assert_eq!(game.run(&Schedule::RoundRobin).unwrap(), 104);
+        assert_eq!(game.robot_positions(), vec![Point(3, 1), Point(5, 1)]);
+
+        let mut game = Game::new(
+            boxes,
+            walls,
+            robots([&[Direction::Right], &[Direction::Right]]),
+        );
+        let schedule = Schedule::Timestamped(vec![
+            Timing {
+                offset: 1,
+                interval: 1,
+            },
+            Timing {
+                offset: 0,
+                interval: 1,
+            },
+        ]);
+        let frames = game
+            .replay(&schedule)
+            .unwrap()
+            .map(|frame| frame.lines().nth(1).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(frames, vec!["#@O@..#", "#@O.@.#", "#.@O@.#"]);
+        assert_eq!(game.run(&schedule).unwrap(), 103);
+        assert!(game.run(&Schedule::Timestamped(Vec::new())).is_err());
     }
 }
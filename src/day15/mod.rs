@@ -1,19 +1,68 @@
 use std::error::Error;
 
-use crate::Puzzle;
+use game::{Anchor, Game, RobotMode, Score};
+use schedule::{Schedule, Timing};
 
-mod game;
-mod parse;
+use crate::{util::Point, Puzzle};
+
+pub mod game;
+pub mod parse;
+pub mod schedule;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+// Every frame of the replay, separated by blank lines
+fn frames(game: &Game, schedule: &Schedule) -> Result<String, Box<dyn Error>> {
+    Ok(game.replay(schedule)?.collect::<Vec<_>>().join("\n\n"))
+}
+
+fn final_positions(mut game: Game, schedule: &Schedule) -> Result<String, Box<dyn Error>> {
+    game.run(schedule)?;
+
+    Ok(format!(
+        "robots end at {}",
+        game.robot_positions()
+            .iter()
+            .map(|Point(x, y)| format!("{x},{y}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ))
+}
+
+// GPS score measured to `anchor` with the puzzle's weights
+fn score(anchor: Anchor) -> Score {
+    Score {
+        anchor,
+        ..Score::default()
+    }
+}
+
+#[derive(Default)]
+pub struct Part1 {
+    anchor: Anchor,
+    robot_mode: RobotMode,
+    schedule: Schedule,
+}
 
 impl Part1 {
-    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, mut game) = parse::parse_input(input).map_err(|e| e.to_owned())?;
+    pub fn with_settings(anchor: Anchor, robot_mode: RobotMode, timings: Vec<Timing>) -> Self {
+        Self {
+            anchor,
+            robot_mode,
+            schedule: Schedule::with_timings(timings),
+        }
+    }
+
+    fn game(&self, input: &str) -> Result<Game, Box<dyn Error>> {
+        let (_, game) = parse::parse_input(input).map_err(|e| e.to_owned())?;
 
-        Ok(game.play())
+        Ok(game
+            .with_score(score(self.anchor))
+            .with_robot_mode(self.robot_mode))
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+        self.game(input)?.run(&self.schedule)
     }
 }
 
@@ -21,17 +70,55 @@ impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
         self.solve_input(INPUT).map(|res| res.to_string())
     }
+
+    // The warehouse before and after every move
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        frames(&self.game(INPUT)?, &self.schedule).map(Some)
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        final_positions(self.game(INPUT)?, &self.schedule).map(Some)
+    }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    expansion: Option<(i64, i64)>,
+    anchor: Anchor,
+    robot_mode: RobotMode,
+    schedule: Schedule,
+}
 
 impl Part2 {
-    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_settings(
+        expansion: Option<(i64, i64)>,
+        anchor: Anchor,
+        robot_mode: RobotMode,
+        timings: Vec<Timing>,
+    ) -> Self {
+        Self {
+            expansion,
+            anchor,
+            robot_mode,
+            schedule: Schedule::with_timings(timings),
+        }
+    }
+
+    fn game(&self, input: &str) -> Result<Game, Box<dyn Error>> {
         let (_, game) = parse::parse_input(input).map_err(|e| e.to_owned())?;
 
-        let mut game = game.expand();
+        let game = match self.expansion {
+            Some((width, height)) => game.expand_by(width, height)?,
+            None => game.expand()?,
+        };
+
+        Ok(game
+            .with_score(score(self.anchor))
+            .with_robot_mode(self.robot_mode))
+    }
 
-        Ok(game.play())
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+        self.game(input)?.run(&self.schedule)
     }
 }
 
@@ -39,6 +126,15 @@ impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
         self.solve_input(INPUT).map(|res| res.to_string())
     }
+
+    // The expanded warehouse before and after every move
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        frames(&self.game(INPUT)?, &self.schedule).map(Some)
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        final_positions(self.game(INPUT)?, &self.schedule).map(Some)
+    }
 }
 
 #[cfg(test)]
@@ -50,16 +146,59 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let result = Part1.solve_input(TEST_INPUT).unwrap();
+        let result = Part1::default().solve_input(TEST_INPUT).unwrap();
         assert_eq!(result, 10092);
 
-        let result = Part1.solve_input(TEST_INPUT_2).unwrap();
+        let result = Part1::default().solve_input(TEST_INPUT_2).unwrap();
         assert_eq!(result, 2028);
+
+        let game = Part1::default().game(TEST_INPUT_2).unwrap();
+        let replay = frames(&game, &Schedule::RoundRobin).unwrap();
+        assert_eq!(replay.split("\n\n").count(), 16);
+        assert!(replay.ends_with(
+            "########\n#....OO#\n##.....#\n#.....O#\n#.#O@..#\n#...O..#\n#...O..#\n########"
+        ));
+
+        let part = Part1::with_settings(Anchor::BottomRight, RobotMode::Blocking, Vec::new());
+        assert_eq!(part.solve_input(TEST_INPUT_2).unwrap(), 2028);
+
+        let game = part.game(TEST_INPUT_2).unwrap();
+        assert_eq!(
+            final_positions(game, &part.schedule).unwrap(),
+            "robots end at 4,4"
+        );
+
+        // A single robot only needs a timing that starts it off
+        let timing = Timing {
+            offset: 5,
+            interval: 2,
+        };
+        let part = Part1::with_settings(Anchor::TopLeft, RobotMode::Pushable, vec![timing]);
+        assert_eq!(part.solve_input(TEST_INPUT_2).unwrap(), 2028);
+
+        let part = Part1::with_settings(Anchor::TopLeft, RobotMode::Blocking, vec![timing; 2]);
+        assert!(part.solve_input(TEST_INPUT_2).is_err());
     }
 
     #[test]
     fn test_part2() {
-        let result = Part2.solve_input(TEST_INPUT).unwrap();
+        let result = Part2::default().solve_input(TEST_INPUT).unwrap();
         assert_eq!(result, 9021);
+
+        let part = Part2::with_settings(
+            Some((2, 1)),
+            Anchor::TopLeft,
+            RobotMode::Blocking,
+            Vec::new(),
+        );
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 9021);
+
+        let part = Part2::with_settings(
+            Some((0, 1)),
+            Anchor::TopLeft,
+            RobotMode::Blocking,
+            Vec::new(),
+        );
+        assert!(part.solve_input(TEST_INPUT).is_err());
     }
 }
@@ -1,62 +1,176 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::newline,
-    combinator::map,
-    multi::{many0, many1, separated_list0},
-    sequence::{delimited, tuple},
+    character::complete::{i64, newline, satisfy, u64},
+    combinator::{all_consuming, map, map_res},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{pair, separated_pair, tuple},
     IResult,
 };
 
-use crate::util::{iter_2d, Direction};
+use crate::util::{iter_2d, Direction, Point};
 
-use super::game::{Game, GameBox};
+use super::{
+    game::{Game, GameBox, Robot},
+    schedule::Timing,
+};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum ParseTile {
     Wall,
     Box,
+    BoxLeft,
+    BoxRight,
+    // Cells with the same label that touch belong to one box
+    Shape(char),
     Robot,
 }
 
 pub fn parse_input(input: &str) -> IResult<&str, Game> {
-    map(
-        tuple((parse_tile_list, many0(newline), parse_instruction_list)),
-        |(tiles, _, instructions)| {
-            let boxes = iter_2d(&tiles)
-                .filter(|(_, &tile)| tile == Some(ParseTile::Box))
-                .map(|(point, _)| GameBox::new(HashSet::from([point])))
-                .collect::<Vec<_>>();
+    map_res(
+        tuple((parse_tile_list, many0(newline), parse_instruction_streams)),
+        |(tiles, _, streams)| -> Result<_, String> {
+            let boxes = parse_boxes(
+                &iter_2d(&tiles)
+                    .filter_map(|(point, &tile)| tile.map(|tile| (point, tile)))
+                    .collect(),
+            )?;
 
             let wall_positions = iter_2d(&tiles)
                 .filter(|(_, &tile)| tile == Some(ParseTile::Wall))
                 .map(|(point, _)| point)
                 .collect::<HashSet<_>>();
 
-            let robot_position = iter_2d(&tiles)
-                .find(|(_, &tile)| tile == Some(ParseTile::Robot))
+            let robot_positions = iter_2d(&tiles)
+                .filter(|(_, &tile)| tile == Some(ParseTile::Robot))
                 .map(|(point, _)| point)
-                .unwrap();
+                .collect::<Vec<_>>();
+
+            if robot_positions.is_empty() {
+                return Err("Robot not found".to_string());
+            }
+
+            // A single stream is shared by every robot, otherwise there is one per robot
+            let robots = match streams.len() {
+                1 => robot_positions
+                    .into_iter()
+                    .map(|position| Robot::new(position, streams[0].clone()))
+                    .collect(),
+                n if n == robot_positions.len() => robot_positions
+                    .into_iter()
+                    .zip(streams)
+                    .map(|(position, instructions)| Robot::new(position, instructions))
+                    .collect(),
+                n => {
+                    return Err(format!(
+                        "Expected {} instruction streams, got {}",
+                        robot_positions.len(),
+                        n
+                    ))
+                }
+            };
 
-            Game::new(boxes, wall_positions, robot_position, instructions)
+            Ok(Game::new(boxes, wall_positions, robots))
         },
     )(input)
 }
 
+fn parse_boxes(tiles: &HashMap<Point, ParseTile>) -> Result<Vec<GameBox>, String> {
+    let mut points = tiles.keys().copied().collect::<Vec<_>>();
+    points.sort_by_key(|&Point(x, y)| (y, x));
+
+    let mut visited = HashSet::new();
+    let mut boxes = Vec::new();
+
+    for point in points {
+        match tiles[&point] {
+            ParseTile::Box => boxes.push(GameBox::new(HashSet::from([point]))),
+            ParseTile::BoxLeft => {
+                if tiles.get(&(point + Direction::Right)) != Some(&ParseTile::BoxRight) {
+                    return Err(format!("Unmatched box edge at {}", point));
+                }
+
+                boxes.push(GameBox::new(HashSet::from([
+                    point,
+                    point + Direction::Right,
+                ])));
+            }
+            ParseTile::BoxRight
+                if tiles.get(&(point + Direction::Left)) != Some(&ParseTile::BoxLeft) =>
+            {
+                return Err(format!("Unmatched box edge at {}", point));
+            }
+            ParseTile::Shape(label) if !visited.contains(&point) => {
+                let mut shape = HashSet::from([point]);
+                let mut stack = vec![point];
+
+                while let Some(point) = stack.pop() {
+                    for next in Direction::all().map(|dir| point + dir) {
+                        if tiles.get(&next) == Some(&ParseTile::Shape(label)) && shape.insert(next)
+                        {
+                            stack.push(next);
+                        }
+                    }
+                }
+
+                visited.extend(shape.iter().copied());
+                boxes.push(GameBox::new(shape));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(boxes)
+}
+
+pub fn parse_expansion(input: &str) -> Result<(i64, i64), String> {
+    // "3x2", as given on the command line
+    all_consuming(separated_pair(
+        i64::<_, nom::error::Error<_>>,
+        tag("x"),
+        i64,
+    ))(input)
+    .map(|(_, factors)| factors)
+    .map_err(|_| format!("Invalid expansion '{input}', expected WIDTHxHEIGHT"))
+}
+
+pub fn parse_timing(input: &str) -> Result<Timing, String> {
+    // "2+3", as given on the command line
+    all_consuming(separated_pair(
+        u64::<_, nom::error::Error<_>>,
+        tag("+"),
+        u64,
+    ))(input)
+    .map(|(_, (offset, interval))| Timing { offset, interval })
+    .map_err(|_| format!("Invalid timing '{input}', expected OFFSET+INTERVAL"))
+}
+
 fn parse_tile_list(input: &str) -> IResult<&str, Vec<Vec<Option<ParseTile>>>> {
     separated_list0(newline, many0(parse_tile))(input)
 }
 
+fn parse_instruction_streams(input: &str) -> IResult<&str, Vec<Vec<Direction>>> {
+    separated_list1(pair(newline, many1(newline)), parse_instruction_list)(input)
+}
+
 fn parse_instruction_list(input: &str) -> IResult<&str, Vec<Direction>> {
-    many1(delimited(many0(newline), parse_instruction, many0(newline)))(input)
+    map(
+        separated_list1(newline, many1(parse_instruction)),
+        |lines| lines.concat(),
+    )(input)
 }
 
 fn parse_tile(input: &str) -> IResult<&str, Option<ParseTile>> {
     alt((
         map(tag("#"), |_| Some(ParseTile::Wall)),
         map(tag("O"), |_| Some(ParseTile::Box)),
+        map(tag("["), |_| Some(ParseTile::BoxLeft)),
+        map(tag("]"), |_| Some(ParseTile::BoxRight)),
+        map(satisfy(|c| c.is_ascii_digit()), |c| {
+            Some(ParseTile::Shape(c))
+        }),
         map(tag("@"), |_| Some(ParseTile::Robot)),
         map(tag("."), |_| None),
     ))(input)
@@ -92,9 +206,31 @@ mod tests {
         assert_eq!(parse_tile("#"), Ok(("", Some(ParseTile::Wall))));
         assert_eq!(parse_tile("O"), Ok(("", Some(ParseTile::Box))));
         assert_eq!(parse_tile("@"), Ok(("", Some(ParseTile::Robot))));
+        assert_eq!(parse_tile("["), Ok(("", Some(ParseTile::BoxLeft))));
+        assert_eq!(parse_tile("7"), Ok(("", Some(ParseTile::Shape('7')))));
         assert_eq!(parse_tile("."), Ok(("", None)));
     }
 
+    #[test]
+    fn test_parse_expansion() {
+        assert_eq!(parse_expansion("3x2"), Ok((3, 2)));
+        assert!(parse_expansion("3").is_err());
+        assert!(parse_expansion("3x2x1").is_err());
+    }
+
+    #[test]
+    fn test_parse_timing() {
+        assert_eq!(
+            parse_timing("2+3"),
+            Ok(Timing {
+                offset: 2,
+                interval: 3
+            })
+        );
+        assert!(parse_timing("2").is_err());
+        assert!(parse_timing("-2+3").is_err());
+    }
+
     #[test]
     fn test_parse_tile_list() {
         let (_, tiles) = parse_tile_list("#@.O").unwrap();
@@ -114,6 +250,40 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let (_, game) = parse_input(TEST_INPUT).unwrap();
-        assert_eq!(game.robot_position, Point(4, 4),)
+        assert_eq!(game.robot_positions(), vec![Point(4, 4)]);
+    }
+
+    #[test]
+    fn test_parse_shapes() {
+        let (_, game) = parse_input("#######\n#.[]..#\n#.11@.#\n#..1..#\n#######\n\n<").unwrap();
+        assert_eq!(game.render(), "#######\n#.[]..#\n#.[┐@.#\n#..╵..#\n#######");
+        assert_eq!(game.score().unwrap(), 102 + 202);
+
+        assert!(parse_input("#[.@#\n\n<").is_err());
+        assert!(parse_input("#.]@#\n\n<").is_err());
+    }
+
+    #[test]
+    fn test_parse_instruction_streams() {
+        let (_, streams) = parse_instruction_streams("^^\n>\n\n<\n").unwrap();
+        assert_eq!(
+            streams,
+            vec![
+                vec![Direction::Up, Direction::Up, Direction::Right],
+                vec![Direction::Left]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple_robots() {
+        let (_, game) = parse_input("#@.@#\n\n>\n\n<<").unwrap();
+        assert_eq!(game.robot_positions(), vec![Point(1, 0), Point(3, 0)]);
+
+        let (_, game) = parse_input("#@.@#\n\n>").unwrap();
+        assert_eq!(game.robot_positions().len(), 2);
+
+        assert!(parse_input("#@.@#\n\n>\n\n<\n\n<").is_err());
+        assert!(parse_input("#...#\n\n>").is_err());
     }
 }
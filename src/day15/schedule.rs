@@ -0,0 +1,118 @@
+use std::error::Error;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Timing {
+    pub offset: u64,
+    pub interval: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum Schedule {
+    // One instruction per robot per round, in robot order
+    #[default]
+    RoundRobin,
+    // A robot's n-th instruction runs at `offset + n * interval`; ties go to the lower robot
+    Timestamped(Vec<Timing>),
+}
+
+impl Schedule {
+    // Timestamped when any timings are given, round robin otherwise
+    pub fn with_timings(timings: Vec<Timing>) -> Self {
+        if timings.is_empty() {
+            Schedule::RoundRobin
+        } else {
+            Schedule::Timestamped(timings)
+        }
+    }
+
+    // Order in which (robot, instruction index) pairs are executed
+    pub fn order(&self, stream_lens: &[usize]) -> Result<Vec<(usize, usize)>, Box<dyn Error>> {
+        match self {
+            Schedule::RoundRobin => {
+                let rounds = stream_lens.iter().copied().max().unwrap_or(0);
+
+                Ok((0..rounds)
+                    .flat_map(|index| {
+                        stream_lens
+                            .iter()
+                            .enumerate()
+                            .filter(move |&(_, &len)| index < len)
+                            .map(move |(robot, _)| (robot, index))
+                    })
+                    .collect())
+            }
+            Schedule::Timestamped(timings) => {
+                if timings.len() != stream_lens.len() {
+                    return Err(format!(
+                        "Expected {} timings, got {}",
+                        stream_lens.len(),
+                        timings.len()
+                    )
+                    .into());
+                }
+
+                if timings.iter().any(|timing| timing.interval == 0) {
+                    return Err("Interval must be non-zero".into());
+                }
+
+                let mut events = timings
+                    .iter()
+                    .zip(stream_lens)
+                    .enumerate()
+                    .flat_map(|(robot, (timing, &len))| {
+                        (0..len).map(move |index| {
+                            (timing.offset + index as u64 * timing.interval, robot, index)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                events.sort_unstable();
+
+                Ok(events
+                    .into_iter()
+                    .map(|(_, robot, index)| (robot, index))
+                    .collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin() {
+        assert_eq!(
+            Schedule::RoundRobin.order(&[2, 1, 3]).unwrap(),
+            vec![(0, 0), (1, 0), (2, 0), (0, 1), (2, 1), (2, 2)]
+        );
+        assert!(Schedule::RoundRobin.order(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_timestamped() {
+        let schedule = Schedule::Timestamped(vec![
+            Timing {
+                offset: 0,
+                interval: 3,
+            },
+            Timing {
+                offset: 1,
+                interval: 1,
+            },
+        ]);
+
+        assert_eq!(
+            schedule.order(&[2, 4]).unwrap(),
+            vec![(0, 0), (1, 0), (1, 1), (0, 1), (1, 2), (1, 3)]
+        );
+        assert!(schedule.order(&[1]).is_err());
+
+        let schedule = Schedule::Timestamped(vec![Timing {
+            offset: 0,
+            interval: 0,
+        }]);
+        assert!(schedule.order(&[1]).is_err());
+    }
+}
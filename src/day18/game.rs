@@ -22,9 +22,12 @@ impl Game {
                 .filter(|point| {
                     if self.corrupted_points.contains(point) {
                         false
-                    } else { !(point.0 < 0
-                        || point.1 < 0
-                        || point.0 >= self.size.0 || point.1 >= self.size.1) }
+                    } else {
+                        !(point.0 < 0
+                            || point.1 < 0
+                            || point.0 >= self.size.0
+                            || point.1 >= self.size.1)
+                    }
                 })
         }) {
             return Some(len);
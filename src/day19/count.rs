@@ -0,0 +1,116 @@
+use std::{fmt, ops::AddAssign};
+
+// Arbitrary-precision arrangement count; counting only ever adds
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Count {
+    // Little-endian base 2^64 digits, without trailing zeros
+    digits: Vec<u64>,
+}
+
+impl From<u128> for Count {
+    fn from(value: u128) -> Self {
+        let digits = [value as u64, (value >> 64) as u64];
+        let len = digits.iter().rposition(|&d| d != 0).map_or(0, |i| i + 1);
+
+        Self {
+            digits: digits[..len].to_vec(),
+        }
+    }
+}
+
+impl AddAssign<&Count> for Count {
+    fn add_assign(&mut self, other: &Count) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+
+        let mut carry = false;
+
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            let addend = other.digits.get(i).copied().unwrap_or(0);
+
+            if addend == 0 && !carry && i >= other.digits.len() {
+                break;
+            }
+
+            let (sum, overflow_a) = digit.overflowing_add(addend);
+            let (sum, overflow_b) = sum.overflowing_add(carry as u64);
+
+            *digit = sum;
+            carry = overflow_a || overflow_b;
+        }
+
+        if carry {
+            self.digits.push(1);
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        // Split into base 10^19 chunks by repeated long division, least significant first
+        let mut digits = self.digits.clone();
+        let mut chunks = Vec::new();
+
+        while !digits.is_empty() {
+            let mut remainder = 0u128;
+
+            for digit in digits.iter_mut().rev() {
+                let value = (remainder << 64) | *digit as u128;
+                *digit = (value / CHUNK as u128) as u64;
+                remainder = value % CHUNK as u128;
+            }
+
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+
+            chunks.push(remainder as u64);
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{first}")?;
+
+                for chunk in rest.iter().rev() {
+                    write!(f, "{chunk:019}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_test() {
+        let mut count = Count::from(u128::MAX);
+        count += &Count::from(1);
+        assert_eq!(count.to_string(), "340282366920938463463374607431768211456");
+
+        count += &Count::from(u128::MAX);
+        assert_eq!(count.to_string(), "680564733841876926926749214863536422911");
+
+        let mut zero = Count::default();
+        assert_eq!(zero.to_string(), "0");
+
+        zero += &Count::from(0);
+        assert_eq!(zero, Count::from(0));
+    }
+
+    #[test]
+    fn display_test() {
+        assert_eq!(Count::from(16).to_string(), "16");
+        assert_eq!(
+            Count::from(10_000_000_000_000_000_000).to_string(),
+            "10000000000000000000"
+        );
+    }
+}
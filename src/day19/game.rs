@@ -1,41 +1,347 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::HashMap, error::Error, iter};
+
+use super::{count::Count, trie::Trie};
+
+// Cheapest cost and back pointer for each usage state at one position
+type Layer = HashMap<Vec<u64>, (u64, Option<(Vec<u64>, usize)>)>;
+
+struct Towel {
+    pattern: String,
+    cost: u64,
+    // None for an unlimited supply
+    stock: Option<u64>,
+}
 
 pub struct Game {
-    towels_by_length: HashMap<usize, HashSet<String>>,
+    towels: Vec<Towel>,
+    trie: Trie,
 }
 
 impl Game {
+    // Every towel starts out costing 1 with an unlimited supply; repeats are merged
     pub fn new(towels: &[&str]) -> Self {
-        let mut towels_by_len: HashMap<usize, HashSet<String>> = HashMap::new();
-
-        for towel in towels {
-            let length = towel.len();
-            towels_by_len
-                .entry(length)
-                .or_default()
-                .insert(towel.to_string());
+        let mut unique: Vec<&str> = Vec::new();
+
+        for &towel in towels {
+            if !unique.contains(&towel) {
+                unique.push(towel);
+            }
         }
 
         Self {
-            towels_by_length: towels_by_len,
+            towels: unique
+                .iter()
+                .map(|towel| Towel {
+                    pattern: towel.to_string(),
+                    cost: 1,
+                    stock: None,
+                })
+                .collect(),
+            trie: Trie::new(&unique),
         }
     }
 
-    pub fn design_count(&self, pattern: &str) -> u64 {
-        let mut counts = vec![0u64; pattern.len() + 1];
-        counts[0] = 1;
+    pub fn set_cost(&mut self, pattern: &str, cost: u64) -> Result<(), Box<dyn Error>> {
+        self.find_towel(pattern)?.cost = cost;
+
+        Ok(())
+    }
+
+    pub fn set_stock(&mut self, pattern: &str, stock: u64) -> Result<(), Box<dyn Error>> {
+        self.find_towel(pattern)?.stock = Some(stock);
+
+        Ok(())
+    }
+
+    fn find_towel(&mut self, pattern: &str) -> Result<&mut Towel, Box<dyn Error>> {
+        self.towels
+            .iter_mut()
+            .find(|towel| towel.pattern == pattern)
+            .ok_or_else(|| format!("Unknown towel {pattern}").into())
+    }
+
+    pub fn design_count(&self, pattern: &str) -> Count {
+        let mut counts = vec![Count::default(); pattern.len() + 1];
+        counts[0] = Count::from(1);
+
+        // Occurrences come in order of their end, so every count is final before it is read
+        for (end, _, length) in self.trie.occurrences(pattern.as_bytes()) {
+            let (done, rest) = counts.split_at_mut(end);
+            rest[0] += &done[end - length];
+        }
+
+        counts.swap_remove(pattern.len())
+    }
+
+    pub fn is_possible(&self, pattern: &str) -> bool {
+        self.completable(pattern)[0]
+    }
+
+    // Lazily yields every arrangement, never descending into a dead end
+    pub fn arrangements<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = Vec<&'a str>> + 'a {
+        let completable = self.completable(pattern);
+        let mut stack = vec![(0, Vec::new())];
+
+        iter::from_fn(move || {
+            while let Some((start, path)) = stack.pop() {
+                if start == pattern.len() {
+                    return Some(path.iter().map(|&towel| self.towel(towel)).collect());
+                }
+
+                let next = self
+                    .matches(pattern, start)
+                    .filter(|&(_, length)| completable[start + length])
+                    .collect::<Vec<_>>();
+
+                // Reversed so that shorter towels come out first
+                for (towel, length) in next.into_iter().rev() {
+                    let mut path = path.clone();
+                    path.push(towel);
+                    stack.push((start + length, path));
+                }
+            }
+
+            None
+        })
+    }
+
+    pub fn fewest_towels(&self, pattern: &str) -> Option<Vec<&str>> {
+        // Fewest towels to reach each position, with the position and towel it was reached from
+        let mut best: Vec<Option<(usize, usize, usize)>> = vec![None; pattern.len() + 1];
+        best[0] = Some((0, 0, 0));
 
         for i in 0..pattern.len() {
-            for (&length, towels) in self.towels_by_length.iter() {
-                if let Some(sub_pattern) = pattern.get(i..i + length) {
-                    if towels.contains(sub_pattern) {
-                        counts[i + length] += counts[i];
+            let Some((count, _, _)) = best[i] else {
+                continue;
+            };
+
+            for (towel, length) in self.matches(pattern, i) {
+                let end = i + length;
+
+                if best[end].is_none_or(|(other, _, _)| count + 1 < other) {
+                    best[end] = Some((count + 1, i, towel));
+                }
+            }
+        }
+
+        best[pattern.len()]?;
+
+        let mut arrangement = Vec::new();
+        let mut position = pattern.len();
+
+        while position > 0 {
+            let (_, previous, towel) = best[position]?;
+            arrangement.push(self.towel(towel));
+            position = previous;
+        }
+
+        arrangement.reverse();
+
+        Some(arrangement)
+    }
+
+    // Longest start of the pattern that towels can cover exactly
+    pub fn longest_prefix<'p>(&self, pattern: &'p str) -> &'p str {
+        let mut reachable = vec![false; pattern.len() + 1];
+        reachable[0] = true;
+
+        let mut longest = 0;
+
+        for i in 0..pattern.len() {
+            if !reachable[i] {
+                continue;
+            }
+
+            longest = i;
+
+            for (_, length) in self.matches(pattern, i) {
+                reachable[i + length] = true;
+            }
+        }
+
+        if reachable[pattern.len()] {
+            longest = pattern.len();
+        }
+
+        &pattern[..longest]
+    }
+
+    pub fn min_cost(&self, pattern: &str) -> Option<u64> {
+        self.cheapest_arrangement(pattern).map(|(cost, _)| cost)
+    }
+
+    // Cheapest arrangement that uses no towel more often than it is stocked
+    pub fn cheapest_arrangement(&self, pattern: &str) -> Option<(u64, Vec<&str>)> {
+        let (cost, mut builds) = self.cheapest_build(&[pattern])?;
+
+        Some((cost, builds.pop()?))
+    }
+
+    // One arrangement per design drawing on a single shared inventory, if there is one
+    pub fn build_all<'a>(&'a self, patterns: &[&str]) -> Option<Vec<Vec<&'a str>>> {
+        self.cheapest_build(patterns).map(|(_, builds)| builds)
+    }
+
+    // Towels that appear in no arrangement of any of the designs
+    pub fn useless_towels(&self, patterns: &[&str]) -> Vec<&str> {
+        let mut useful = vec![false; self.towels.len()];
+
+        for pattern in patterns {
+            let completable = self.completable(pattern);
+            let mut reachable = vec![false; pattern.len() + 1];
+            reachable[0] = true;
+
+            for (end, towel, length) in self.trie.occurrences(pattern.as_bytes()) {
+                if reachable[end - length] {
+                    reachable[end] = true;
+                    useful[towel] |= completable[end];
+                }
+            }
+        }
+
+        (0..self.towels.len())
+            .filter(|&towel| !useful[towel])
+            .map(|towel| self.towel(towel))
+            .collect()
+    }
+
+    // Cheapest way to build every design in turn from one shared inventory. A state is a
+    // position plus how many of each towel with a binding stock limit have been used, so
+    // the work is O(total length * matches per position * product of (stock + 1)) over
+    // just those towels; a towel stocked at least as often as it occurs is left out
+    fn cheapest_build(&self, patterns: &[&str]) -> Option<(u64, Vec<Vec<&str>>)> {
+        let slots = self.stock_slots(patterns);
+        let slot_count = slots.iter().flatten().count();
+
+        let mut layers: Vec<Vec<Layer>> = Vec::new();
+        let mut start = Layer::from([(vec![0; slot_count], (0, None))]);
+
+        for pattern in patterns {
+            let completable = self.completable(pattern);
+            let mut layer = vec![Layer::new(); pattern.len() + 1];
+            layer[0] = start;
+
+            for position in 0..pattern.len() {
+                if !completable[position] {
+                    continue;
+                }
+
+                let (done, rest) = layer.split_at_mut(position + 1);
+
+                for (used, &(cost, _)) in done[position].iter() {
+                    for (towel, length) in self.matches(pattern, position) {
+                        if !completable[position + length] {
+                            continue;
+                        }
+
+                        let mut next = used.clone();
+
+                        if let Some(slot) = slots[towel] {
+                            if self.towels[towel].stock.is_some_and(|s| next[slot] >= s) {
+                                continue;
+                            }
+
+                            next[slot] += 1;
+                        }
+
+                        let cost = cost + self.towels[towel].cost;
+                        let entry = rest[length - 1].entry(next).or_insert((u64::MAX, None));
+
+                        if cost < entry.0 {
+                            *entry = (cost, Some((used.clone(), towel)));
+                        }
                     }
                 }
             }
+
+            // Later designs start from whatever stock each finished state leaves
+            start = layer[pattern.len()]
+                .iter()
+                .map(|(used, &(cost, _))| (used.clone(), (cost, None)))
+                .collect();
+
+            if start.is_empty() {
+                return None;
+            }
+
+            layers.push(layer);
+        }
+
+        let (mut used, &(cost, _)) = start.iter().min_by_key(|(_, &(cost, _))| cost)?;
+        let mut builds = Vec::new();
+
+        for layer in layers.iter().rev() {
+            let mut build = Vec::new();
+            let mut position = layer.len() - 1;
+
+            while position > 0 {
+                let (_, Some((previous, towel))) = &layer[position][used] else {
+                    unreachable!();
+                };
+
+                build.push(self.towel(*towel));
+                position -= self.towels[*towel].pattern.len();
+                used = previous;
+            }
+
+            build.reverse();
+            builds.push(build);
+        }
+
+        builds.reverse();
+
+        Some((cost, builds))
+    }
+
+    // Index into the usage state for every towel whose stock could actually run out
+    fn stock_slots(&self, patterns: &[&str]) -> Vec<Option<usize>> {
+        let mut occurrences = vec![0; self.towels.len()];
+
+        for pattern in patterns {
+            for (_, towel, _) in self.trie.occurrences(pattern.as_bytes()) {
+                occurrences[towel] += 1;
+            }
+        }
+
+        let mut slot_count = 0;
+
+        self.towels
+            .iter()
+            .zip(occurrences)
+            .map(|(towel, count)| match towel.stock {
+                Some(stock) if stock < count => {
+                    slot_count += 1;
+                    Some(slot_count - 1)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn towel(&self, index: usize) -> &str {
+        &self.towels[index].pattern
+    }
+
+    fn matches<'a>(
+        &'a self,
+        pattern: &'a str,
+        start: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.trie.prefixes(&pattern.as_bytes()[start..])
+    }
+
+    // Whether the rest of the pattern from each position can be covered
+    fn completable(&self, pattern: &str) -> Vec<bool> {
+        let mut completable = vec![false; pattern.len() + 1];
+        completable[pattern.len()] = true;
+
+        for i in (0..pattern.len()).rev() {
+            completable[i] = self
+                .matches(pattern, i)
+                .any(|(_, length)| completable[i + length]);
         }
 
-        counts[pattern.len()]
+        completable
     }
 }
 
@@ -43,24 +349,185 @@ impl Game {
 mod tests {
     use super::*;
 
+    const TOWELS: [&str; 8] = ["r", "wr", "b", "g", "bwu", "rb", "gb", "br"];
+
     #[test]
     fn design_count_test() {
-        let game = Game::new(&["r", "wr", "b", "g", "bwu", "rb", "gb", "br"]);
+        let game = Game::new(&TOWELS);
+
+        assert_eq!(game.design_count("brwrr"), Count::from(2));
+
+        assert_eq!(game.design_count("bggr"), Count::from(1));
+
+        assert_eq!(game.design_count("gbbr"), Count::from(4));
+
+        assert_eq!(game.design_count("rrbgbr"), Count::from(6));
+
+        assert_eq!(game.design_count("ubwu"), Count::from(0));
+
+        assert_eq!(game.design_count("bwurrg"), Count::from(1));
+
+        assert_eq!(game.design_count("brgr"), Count::from(2));
+
+        assert_eq!(game.design_count("bbrgwb"), Count::from(0));
+    }
 
-        assert_eq!(game.design_count("brwrr"), 2);
+    #[test]
+    fn design_count_large_test() {
+        let game = Game::new(&["a", "aa"]);
+
+        // Fibonacci numbers, well past what fits in a u128
+        assert_eq!(
+            game.design_count(&"a".repeat(300)).to_string(),
+            "359579325206583560961765665172189099052367214309267232255589801"
+        );
+
+        let count = game.design_count(&"a".repeat(10000)).to_string();
+        assert_eq!(count.len(), 2090);
+        assert!(count.starts_with("54438373113565281338"));
+        assert!(count.ends_with("60676846711185597501"));
+
+        assert_eq!(
+            game.design_count(&("a".repeat(10000) + "b")),
+            Count::default()
+        );
+    }
+
+    #[test]
+    fn min_cost_test() {
+        let mut game = Game::new(&TOWELS);
+
+        assert_eq!(game.min_cost("gbbr"), Some(2));
+        assert_eq!(game.min_cost("bbrgwb"), None);
+
+        game.set_cost("gb", 5).unwrap();
+        game.set_cost("br", 5).unwrap();
+        assert_eq!(
+            game.cheapest_arrangement("gbbr"),
+            Some((4, vec!["g", "b", "b", "r"]))
+        );
+
+        game.set_stock("b", 1).unwrap();
+        assert_eq!(game.min_cost("gbbr"), Some(7));
+
+        game.set_stock("gb", 0).unwrap();
+        assert_eq!(
+            game.cheapest_arrangement("gbbr"),
+            Some((7, vec!["g", "b", "br"]))
+        );
+
+        game.set_stock("br", 0).unwrap();
+        assert_eq!(game.min_cost("gbbr"), None);
+    }
+
+    #[test]
+    fn build_all_test() {
+        let mut game = Game::new(&TOWELS);
+        game.set_stock("r", 1).unwrap();
+
+        let arrangements = game.build_all(&["brgr", "gbbr"]).unwrap();
+        assert_eq!(arrangements[0].concat(), "brgr");
+        assert_eq!(arrangements[1].concat(), "gbbr");
+        assert_eq!(
+            arrangements.iter().flatten().filter(|&&t| t == "r").count(),
+            1
+        );
+
+        game.set_stock("br", 0).unwrap();
+        assert_eq!(game.build_all(&["brgr", "gbbr"]), None);
+        assert_eq!(game.build_all(&["bbrgwb"]), None);
+        assert_eq!(game.build_all(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn stock_limits_test() {
+        let mut game = Game::new(&["a", "aa", "aaa"]);
 
-        assert_eq!(game.design_count("bggr"), 1);
+        for towel in ["a", "aa", "aaa"] {
+            game.set_stock(towel, 8).unwrap();
+        }
+
+        assert_eq!(game.min_cost(&"a".repeat(44)), Some(20));
+        assert_eq!(game.min_cost(&"a".repeat(48)), Some(24));
+        assert_eq!(game.min_cost(&"a".repeat(49)), None);
+        assert_eq!(game.build_all(&[&"a".repeat(49)]), None);
+
+        let builds = game.build_all(&[&"a".repeat(30), &"a".repeat(18)]).unwrap();
+        assert_eq!(builds.concat().concat().len(), 48);
+        assert_eq!(game.build_all(&[&"a".repeat(30), &"a".repeat(19)]), None);
+    }
+
+    #[test]
+    fn towel_settings_test() {
+        let mut game = Game::new(&["r", "b", "r"]);
+
+        assert!(game.set_cost("g", 2).is_err());
+        assert!(game.set_stock("rr", 2).is_err());
+
+        game.set_stock("r", 2).unwrap();
+        assert_eq!(game.min_cost("rbr"), Some(3));
+        assert_eq!(game.min_cost("rrr"), None);
+        assert_eq!(game.useless_towels(&[]), vec!["r", "b"]);
+    }
+
+    #[test]
+    fn useless_towels_test() {
+        let game = Game::new(&TOWELS);
+
+        assert_eq!(
+            game.useless_towels(&["brwrr", "bggr", "gbbr"]),
+            vec!["bwu", "rb"]
+        );
+        assert_eq!(game.useless_towels(&[]).len(), TOWELS.len());
+    }
+
+    #[test]
+    fn arrangements_test() {
+        let game = Game::new(&TOWELS);
 
-        assert_eq!(game.design_count("gbbr"), 4);
+        assert_eq!(
+            game.arrangements("gbbr").collect::<Vec<_>>(),
+            vec![
+                vec!["g", "b", "b", "r"],
+                vec!["g", "b", "br"],
+                vec!["gb", "b", "r"],
+                vec!["gb", "br"],
+            ]
+        );
+        assert_eq!(game.arrangements("ubwu").count(), 0);
 
-        assert_eq!(game.design_count("rrbgbr"), 6);
+        for pattern in ["brwrr", "rrbgbr", "bwurrg", "brgr"] {
+            assert_eq!(
+                Count::from(game.arrangements(pattern).count() as u128),
+                game.design_count(pattern)
+            );
+            assert!(game
+                .arrangements(pattern)
+                .all(|arrangement| arrangement.concat() == pattern));
+        }
+    }
 
-        assert_eq!(game.design_count("ubwu"), 0);
+    #[test]
+    fn fewest_towels_test() {
+        let game = Game::new(&TOWELS);
 
-        assert_eq!(game.design_count("bwurrg"), 1);
+        assert_eq!(game.fewest_towels("gbbr"), Some(vec!["gb", "br"]));
+        assert_eq!(
+            game.fewest_towels("bwurrg"),
+            Some(vec!["bwu", "r", "r", "g"])
+        );
+        assert_eq!(game.fewest_towels("bbrgwb"), None);
+        assert_eq!(game.fewest_towels(""), Some(vec![]));
+    }
 
-        assert_eq!(game.design_count("brgr"), 2);
+    #[test]
+    fn longest_prefix_test() {
+        let game = Game::new(&TOWELS);
 
-        assert_eq!(game.design_count("bbrgwb"), 0);
+        assert_eq!(game.longest_prefix("bbrgwb"), "bbrg");
+        assert_eq!(game.longest_prefix("ubwu"), "");
+        assert_eq!(game.longest_prefix("brgr"), "brgr");
+        assert!(game.is_possible("brgr"));
+        assert!(!game.is_possible("bbrgwb"));
     }
 }
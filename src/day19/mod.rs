@@ -1,6 +1,9 @@
+mod count;
 mod game;
-mod parse;
+pub mod parse;
+mod trie;
 
+use count::Count;
 use game::Game;
 use parse::parse_input;
 
@@ -8,18 +11,85 @@ use crate::Puzzle;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+// Arrangements listed per design in the part 2 report
+const REPORT_ARRANGEMENTS: usize = 3;
+
+#[derive(Default)]
+pub struct Part1 {
+    costs: Vec<(String, u64)>,
+    stocks: Vec<(String, u64)>,
+}
 
 impl Part1 {
+    pub fn with_towels(costs: Vec<(String, u64)>, stocks: Vec<(String, u64)>) -> Self {
+        Self { costs, stocks }
+    }
+
     fn solve_input(input: &str) -> Result<u64, Box<dyn std::error::Error>> {
         let (_, (towels, patterns)) = parse_input(input).map_err(|e| e.to_owned())?;
 
         let game = Game::new(&towels);
 
-        Ok(patterns
+        Ok(patterns.iter().filter(|p| game.is_possible(p)).count() as u64)
+    }
+
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let (_, (towels, patterns)) = parse_input(input).map_err(|e| e.to_owned())?;
+
+        let mut game = Game::new(&towels);
+
+        for (towel, cost) in self.costs.iter() {
+            game.set_cost(towel, *cost)?;
+        }
+
+        for (towel, stock) in self.stocks.iter() {
+            game.set_stock(towel, *stock)?;
+        }
+
+        let mut lines = patterns
             .iter()
-            .filter(|p| game.design_count(p) > 0)
-            .count() as u64)
+            .map(|p| match game.fewest_towels(p) {
+                Some(arrangement) => {
+                    let cheapest = match game.cheapest_arrangement(p) {
+                        Some((cost, arrangement)) => {
+                            format!("cheapest {cost} with {}", arrangement.join(","))
+                        }
+                        None => "out of stock".to_string(),
+                    };
+
+                    format!("{p}: {} ({cheapest})", arrangement.join(","))
+                }
+                None => format!(
+                    "{p}: impossible, longest buildable prefix '{}'",
+                    game.longest_prefix(p)
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        let possible = patterns
+            .iter()
+            .filter(|p| game.is_possible(p))
+            .copied()
+            .collect::<Vec<_>>();
+
+        lines.push(format!(
+            "useless towels: {}",
+            game.useless_towels(&patterns).join(",")
+        ));
+        lines.push(format!(
+            "stock covers every possible design: {}",
+            game.build_all(&possible).is_some()
+        ));
+        lines.push(format!(
+            "minimum cost of every possible design: {}",
+            possible
+                .iter()
+                .map(|p| game.min_cost(p))
+                .sum::<Option<u64>>()
+                .map_or("out of stock".to_string(), |cost| cost.to_string())
+        ));
+
+        Ok(lines.join("\n"))
     }
 }
 
@@ -27,16 +97,46 @@ impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn std::error::Error>> {
         Ok(Self::solve_input(INPUT)?.to_string())
     }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.report_input(INPUT).map(Some)
+    }
 }
 
 pub struct Part2;
 
 impl Part2 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    fn solve_input(input: &str) -> Result<Count, Box<dyn std::error::Error>> {
+        let (_, (towels, patterns)) = parse_input(input).map_err(|e| e.to_owned())?;
+        let game = Game::new(&towels);
+
+        let mut total = Count::default();
+        for p in patterns.iter() {
+            total += &game.design_count(p);
+        }
+
+        Ok(total)
+    }
+
+    fn report_input(input: &str) -> Result<String, Box<dyn std::error::Error>> {
         let (_, (towels, patterns)) = parse_input(input).map_err(|e| e.to_owned())?;
         let game = Game::new(&towels);
 
-        Ok(patterns.iter().map(|p| game.design_count(p)).sum::<u64>())
+        Ok(patterns
+            .iter()
+            .map(|p| {
+                let count = game.design_count(p);
+
+                let examples = game
+                    .arrangements(p)
+                    .take(REPORT_ARRANGEMENTS)
+                    .map(|arrangement| arrangement.join(","))
+                    .collect::<Vec<_>>();
+
+                format!("{p}: {count} [{}]", examples.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
     }
 }
 
@@ -44,6 +144,10 @@ impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn std::error::Error>> {
         Ok(Self::solve_input(INPUT)?.to_string())
     }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Self::report_input(INPUT).map(Some)
+    }
 }
 
 #[cfg(test)]
@@ -57,8 +161,50 @@ mod tests {
         assert_eq!(Part1::solve_input(TEST_INPUT).unwrap(), 6);
     }
 
+    #[test]
+    fn part1_report_test() {
+        let report = Part1::default().report_input(TEST_INPUT).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "brwrr: br,wr,r (cheapest 3 with br,wr,r)");
+        assert_eq!(lines[4], "ubwu: impossible, longest buildable prefix ''");
+        assert_eq!(
+            lines[7],
+            "bbrgwb: impossible, longest buildable prefix 'bbrg'"
+        );
+        assert_eq!(lines[8], "useless towels: ");
+        assert_eq!(lines[9], "stock covers every possible design: true");
+        assert_eq!(lines[10], "minimum cost of every possible design: 20");
+    }
+
+    #[test]
+    fn part1_report_settings_test() {
+        let part = Part1::with_towels(
+            vec![("br".to_string(), 5)],
+            vec![("r".to_string(), 3), ("b".to_string(), 1)],
+        );
+        let report = part.report_input(TEST_INPUT).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "brwrr: br,wr,r (cheapest 4 with b,r,wr,r)");
+        assert_eq!(lines[9], "stock covers every possible design: false");
+        assert_eq!(lines[10], "minimum cost of every possible design: 23");
+
+        let part = Part1::with_towels(vec![("x".to_string(), 1)], Vec::new());
+        assert!(part.report_input(TEST_INPUT).is_err());
+    }
+
+    #[test]
+    fn part2_report_test() {
+        let report = Part2::report_input(TEST_INPUT).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "brwrr: 2 [b,r,wr,r br,wr,r]");
+        assert_eq!(lines[4], "ubwu: 0 []");
+    }
+
     #[test]
     fn part2_test() {
-        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 16);
+        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), Count::from(16));
     }
 }
@@ -1,6 +1,7 @@
 use nom::{
     bytes::complete::tag,
-    character::complete::{alpha1, newline, space0},
+    character::complete::{alpha1, newline, space0, u64},
+    combinator::all_consuming,
     multi::{many1, separated_list0},
     sequence::{delimited, separated_pair},
     IResult,
@@ -13,3 +14,14 @@ pub fn parse_input(input: &str) -> IResult<&str, (Vec<&str>, Vec<&str>)> {
         separated_list0(newline, alpha1),
     ))(input)
 }
+
+pub fn parse_towel_setting(input: &str) -> Result<(String, u64), String> {
+    // "gb=3", as given on the command line
+    all_consuming(separated_pair(
+        alpha1::<_, nom::error::Error<_>>,
+        tag("="),
+        u64,
+    ))(input)
+    .map(|(_, (towel, value))| (towel.to_string(), value))
+    .map_err(|_| format!("Invalid towel setting '{input}', expected TOWEL=NUMBER"))
+}
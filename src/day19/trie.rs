@@ -0,0 +1,203 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    iter,
+};
+
+#[derive(Default)]
+struct Node {
+    children: HashMap<u8, usize>,
+    towel: Option<usize>,
+    depth: usize,
+    // Longest proper suffix of this node that is also in the trie
+    fail: usize,
+    // Nearest node along the fail links that ends a towel
+    output: Option<usize>,
+}
+
+pub struct Trie {
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    // Towels are identified by their index; a repeated towel keeps its first index
+    pub fn new(towels: &[&str]) -> Self {
+        let mut nodes = vec![Node::default()];
+
+        // An empty towel would match everywhere without covering anything
+        for (index, towel) in towels.iter().enumerate().filter(|(_, t)| !t.is_empty()) {
+            let mut node = 0;
+
+            for &c in towel.as_bytes() {
+                node = match nodes[node].children.get(&c) {
+                    Some(&child) => child,
+                    None => {
+                        let depth = nodes[node].depth + 1;
+                        nodes.push(Node {
+                            depth,
+                            ..Node::default()
+                        });
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(c, child);
+                        child
+                    }
+                };
+            }
+
+            nodes[node].towel.get_or_insert(index);
+        }
+
+        let mut trie = Self { nodes };
+        trie.link();
+
+        trie
+    }
+
+    // Aho-Corasick fail and output links, filled in breadth first so that
+    // every shorter suffix is linked before it is needed
+    fn link(&mut self) {
+        let mut queue = VecDeque::from([0]);
+
+        while let Some(node) = queue.pop_front() {
+            let children = self.nodes[node]
+                .children
+                .iter()
+                .map(|(&c, &child)| (c, child))
+                .collect::<Vec<_>>();
+
+            for (c, child) in children {
+                let fail = if node == 0 {
+                    0
+                } else {
+                    self.step(self.nodes[node].fail, c)
+                };
+
+                let output = if self.nodes[fail].towel.is_some() {
+                    Some(fail)
+                } else {
+                    self.nodes[fail].output
+                };
+
+                self.nodes[child].fail = fail;
+                self.nodes[child].output = output;
+
+                queue.push_back(child);
+            }
+        }
+    }
+
+    fn step(&self, mut node: usize, c: u8) -> usize {
+        loop {
+            if let Some(&child) = self.nodes[node].children.get(&c) {
+                return child;
+            }
+
+            if node == 0 {
+                return 0;
+            }
+
+            node = self.nodes[node].fail;
+        }
+    }
+
+    // Every towel that `text` starts with, as (towel index, length), shortest first
+    pub fn prefixes<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut node = Some(0);
+        let mut chars = text.iter().enumerate();
+
+        iter::from_fn(move || loop {
+            let (i, c) = chars.next()?;
+            let next = self.nodes[node?].children.get(c).copied();
+            node = next;
+
+            if let Some(towel) = self.nodes[next?].towel {
+                return Some((towel, i + 1));
+            }
+        })
+    }
+
+    // Every towel occurring in `text`, as (end, towel index, length), in one pass over the text
+    pub fn occurrences<'a>(
+        &'a self,
+        text: &'a [u8],
+    ) -> impl Iterator<Item = (usize, usize, usize)> + 'a {
+        text.iter()
+            .scan(0, move |node, &c| {
+                *node = self.step(*node, c);
+                Some(*node)
+            })
+            .enumerate()
+            .flat_map(move |(i, node)| {
+                self.ending_at(node)
+                    .map(move |(towel, length)| (i + 1, towel, length))
+            })
+    }
+
+    // Towels that are suffixes of the node's string, longest first
+    fn ending_at(&self, node: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let first = match self.nodes[node].towel {
+            Some(_) => Some(node),
+            None => self.nodes[node].output,
+        };
+
+        iter::successors(first, |&node| self.nodes[node].output).filter_map(|node| {
+            let node = &self.nodes[node];
+            node.towel.map(|towel| (towel, node.depth))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_test() {
+        let trie = Trie::new(&["r", "wr", "b", "rb", "rbg", "r"]);
+
+        assert_eq!(
+            trie.prefixes(b"rbgw").collect::<Vec<_>>(),
+            vec![(0, 1), (3, 2), (4, 3)]
+        );
+        assert_eq!(trie.prefixes(b"wrb").collect::<Vec<_>>(), vec![(1, 2)]);
+        assert_eq!(trie.prefixes(b"g").count(), 0);
+        assert_eq!(trie.prefixes(b"").count(), 0);
+    }
+
+    #[test]
+    fn occurrences_test() {
+        let trie = Trie::new(&["r", "wr", "b", "rb", "rbg", ""]);
+
+        assert_eq!(
+            trie.occurrences(b"wrbg").collect::<Vec<_>>(),
+            vec![(2, 1, 2), (2, 0, 1), (3, 3, 2), (3, 2, 1), (4, 4, 3)]
+        );
+    }
+
+    #[test]
+    fn occurrences_naive_test() {
+        let towels = [
+            "w", "u", "wu", "uwu", "bwu", "rr", "rrr", "gbr", "ubw", "wubrg",
+        ];
+        let trie = Trie::new(&towels);
+
+        let text = b"wubrgwuwubwrrrgbrubwuwubrgrrrrwuuwubgbrwwuubwuggbrrwubrgubwubw\
+                     rrgbrwuwuwubrgbwurrruwbgbrubwwubrgrgbrwubwuuwrrrwubrg";
+
+        let mut expected = (1..=text.len())
+            .flat_map(|end| {
+                towels
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, towel)| text[..end].ends_with(towel.as_bytes()))
+                    .map(move |(index, towel)| (end, index, towel.len()))
+            })
+            .collect::<Vec<_>>();
+        let mut found = trie.occurrences(text).collect::<Vec<_>>();
+
+        expected.sort_unstable();
+        found.sort_unstable();
+
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+    }
+}
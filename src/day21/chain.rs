@@ -0,0 +1,460 @@
+use std::error::Error;
+
+use super::{
+    game::Game,
+    keypad::{Keypad, ACTIVATE},
+};
+
+const CONTROLLER_KEYS: [char; 5] = ['^', '>', 'v', '<', ACTIVATE];
+const PAIR_COUNT: usize = CONTROLLER_KEYS.len() * CONTROLLER_KEYS.len();
+
+const MAX_EXACT_COST: u128 = u128::MAX >> 16;
+
+fn key_index(key: char) -> usize {
+    CONTROLLER_KEYS.iter().position(|&k| k == key).unwrap()
+}
+
+fn pair_index(from: char, to: char) -> usize {
+    key_index(from) * CONTROLLER_KEYS.len() + key_index(to)
+}
+
+fn transitions(keys: &[char]) -> impl Iterator<Item = (char, char)> + '_ {
+    std::iter::once(ACTIVATE)
+        .chain(keys.iter().copied())
+        .zip(keys.iter().copied())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostTable([u128; PAIR_COUNT]);
+
+impl CostTable {
+    pub fn human() -> Self {
+        Self([1; PAIR_COUNT])
+    }
+
+    pub fn get(&self, from: char, to: char) -> u128 {
+        self.0[pair_index(from, to)]
+    }
+
+    fn is_exact(&self) -> bool {
+        self.0.iter().all(|&cost| cost <= MAX_EXACT_COST)
+    }
+
+    pub fn compose(&self, keypad: &Keypad) -> Result<(Self, Sequences), Box<dyn Error>> {
+        if !self.is_exact() {
+            return Err("Overflow".into());
+        }
+
+        let mut costs = [0; PAIR_COUNT];
+        let mut sequences = Sequences::default();
+
+        for from in CONTROLLER_KEYS {
+            for to in CONTROLLER_KEYS {
+                let (cost, keys) = keypad
+                    .cheapest_sequence(from, to, |a, b| self.get(a, b))
+                    .ok_or_else(|| format!("Key '{to}' is not reachable from '{from}'"))?;
+
+                costs[pair_index(from, to)] = cost;
+                sequences.0[pair_index(from, to)] = keys;
+            }
+        }
+
+        Ok((Self(costs), sequences))
+    }
+
+    fn sequence_cost(&self, keys: &[char], modulus: Option<u128>) -> Option<u128> {
+        transitions(keys).try_fold(0, |acc, (from, to)| add(acc, self.get(from, to), modulus))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sequences([Vec<char>; PAIR_COUNT]);
+
+impl Sequences {
+    fn matrix(&self) -> Matrix {
+        let mut matrix = Matrix::zero();
+
+        for (row, keys) in self.0.iter().enumerate() {
+            for (from, to) in transitions(keys) {
+                matrix.0[row][pair_index(from, to)] += 1;
+            }
+        }
+
+        matrix
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix([[u128; PAIR_COUNT]; PAIR_COUNT]);
+
+impl Matrix {
+    fn zero() -> Self {
+        Self([[0; PAIR_COUNT]; PAIR_COUNT])
+    }
+
+    fn identity() -> Self {
+        let mut matrix = Self::zero();
+
+        for i in 0..PAIR_COUNT {
+            matrix.0[i][i] = 1;
+        }
+
+        matrix
+    }
+
+    fn mul(&self, other: &Self, modulus: u128) -> Self {
+        let mut result = Self::zero();
+
+        for i in 0..PAIR_COUNT {
+            for k in 0..PAIR_COUNT {
+                if self.0[i][k] == 0 {
+                    continue;
+                }
+
+                for j in 0..PAIR_COUNT {
+                    let product = mul_mod(self.0[i][k], other.0[k][j], modulus);
+                    result.0[i][j] = add_mod(result.0[i][j], product, modulus);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn pow(&self, mut exponent: u64, modulus: u128) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::identity();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base, modulus);
+            }
+
+            base = base.mul(&base, modulus);
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    fn apply(&self, table: &CostTable, modulus: u128) -> CostTable {
+        let mut costs = [0; PAIR_COUNT];
+
+        for (i, cost) in costs.iter_mut().enumerate() {
+            *cost = (0..PAIR_COUNT).fold(0, |acc, j| {
+                add_mod(acc, mul_mod(self.0[i][j], table.0[j], modulus), modulus)
+            });
+        }
+
+        CostTable(costs)
+    }
+}
+
+fn add(a: u128, b: u128, modulus: Option<u128>) -> Option<u128> {
+    match modulus {
+        Some(m) => Some(add_mod(a, b, m)),
+        None => a.checked_add(b),
+    }
+}
+
+fn add_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    let (a, b) = (a % modulus, b % modulus);
+
+    if a >= modulus - b {
+        a - (modulus - b)
+    } else {
+        a + b
+    }
+}
+
+fn mul_mod(mut a: u128, mut b: u128, modulus: u128) -> u128 {
+    if modulus <= u64::MAX as u128 {
+        return (a % modulus) * (b % modulus) % modulus;
+    }
+
+    let mut result = 0;
+    a %= modulus;
+
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, modulus);
+        }
+
+        a = add_mod(a, a, modulus);
+        b >>= 1;
+    }
+
+    result
+}
+
+pub struct RobotChain {
+    door: Keypad,
+    directional: Keypad,
+}
+
+impl RobotChain {
+    pub fn new(door: Keypad, directional: Keypad) -> Result<Self, Box<dyn Error>> {
+        if !directional.is_directional() {
+            return Err("Robot keypad is not a directional keypad".into());
+        }
+
+        Ok(Self { door, directional })
+    }
+
+    pub fn sequence_len(&self, code: &str, depth: u64) -> Result<u128, Box<dyn Error>> {
+        let mut table = CostTable::human();
+
+        for _ in 0..depth {
+            (table, _) = table.compose(&self.directional)?;
+        }
+
+        self.door_cost(&self.door_sequences(code, &table)?, &table, None)
+    }
+
+    pub fn sequence_len_mod(
+        &self,
+        code: &str,
+        depth: u64,
+        modulus: u128,
+    ) -> Result<u128, Box<dyn Error>> {
+        self.sequence_len_mod_within(code, depth, modulus, u64::MAX)
+    }
+
+    // Every level up to `exact_levels`, or until costs stop fitting, picks its sequences from
+    // exact costs. Deeper levels repeat the last choices once neither the directional nor the
+    // door sequences changed between the last two exact levels. That choices then never change
+    // again is assumed, not proven, and has only been checked against exact costs for the
+    // standard keypads, so other keypads are refused past the exact levels
+    fn sequence_len_mod_within(
+        &self,
+        code: &str,
+        depth: u64,
+        modulus: u128,
+        exact_levels: u64,
+    ) -> Result<u128, Box<dyn Error>> {
+        if modulus == 0 {
+            return Err("Modulus must be positive".into());
+        }
+
+        let mut table = CostTable::human();
+        let mut previous_table = None;
+        let mut sequences = None;
+        let mut is_settled = false;
+        let mut level = 0;
+
+        while level < depth.min(exact_levels) && table.is_exact() {
+            let (next_table, next_sequences) = table.compose(&self.directional)?;
+
+            is_settled = sequences.as_ref() == Some(&next_sequences);
+            previous_table = Some(std::mem::replace(&mut table, next_table));
+            sequences = Some(next_sequences);
+            level += 1;
+        }
+
+        let door_sequences = self.door_sequences(code, &table)?;
+
+        if level == depth {
+            return self.door_cost(&door_sequences, &table, Some(modulus));
+        }
+
+        if !self.is_standard() {
+            return Err(format!(
+                "Depth {depth} needs more than {level} exact levels, which is only supported for the standard keypads"
+            )
+            .into());
+        }
+
+        let is_door_settled = match &previous_table {
+            Some(previous_table) => self.door_sequences(code, previous_table)? == door_sequences,
+            None => false,
+        };
+
+        let Some(sequences) = sequences.filter(|_| is_settled && is_door_settled) else {
+            return Err(format!("Sequences still change after {level} exact levels").into());
+        };
+
+        let table = sequences
+            .matrix()
+            .pow(depth - level, modulus)
+            .apply(&table, modulus);
+
+        self.door_cost(&door_sequences, &table, Some(modulus))
+    }
+
+    fn is_standard(&self) -> bool {
+        self.door == Keypad::numeric() && self.directional == Keypad::directional()
+    }
+
+    pub fn complexity(&self, codes: &[&str], depth: u64) -> Result<u128, Box<dyn Error>> {
+        codes
+            .iter()
+            .map(|code| {
+                (Game::get_numeric_part(code) as u128)
+                    .checked_mul(self.sequence_len(code, depth)?)
+                    .ok_or_else(|| "Overflow".into())
+            })
+            .sum()
+    }
+
+    pub fn complexity_mod(
+        &self,
+        codes: &[&str],
+        depth: u64,
+        modulus: u128,
+    ) -> Result<u128, Box<dyn Error>> {
+        codes.iter().try_fold(0, |acc, code| {
+            let len = self.sequence_len_mod(code, depth, modulus)?;
+
+            Ok(add_mod(
+                acc,
+                mul_mod(Game::get_numeric_part(code) as u128, len, modulus),
+                modulus,
+            ))
+        })
+    }
+
+    // Cheapest key sequence for every door key under `table`
+    fn door_sequences(
+        &self,
+        code: &str,
+        table: &CostTable,
+    ) -> Result<Vec<Vec<char>>, Box<dyn Error>> {
+        let mut current_key = self.door.start();
+
+        code.chars()
+            .map(|key| {
+                let (_, keys) = self
+                    .door
+                    .cheapest_sequence(current_key, key, |a, b| table.get(a, b))
+                    .ok_or_else(|| format!("Key '{key}' is not reachable on the keypad"))?;
+
+                current_key = key;
+                Ok(keys)
+            })
+            .collect()
+    }
+
+    fn door_cost(
+        &self,
+        door_sequences: &[Vec<char>],
+        table: &CostTable,
+        modulus: Option<u128>,
+    ) -> Result<u128, Box<dyn Error>> {
+        door_sequences.iter().try_fold(0, |total, keys| {
+            table
+                .sequence_cost(keys, modulus)
+                .and_then(|cost| add(total, cost, modulus))
+                .ok_or_else(|| "Overflow".into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::robot_chain;
+    use super::*;
+    use crate::day21::game::Game;
+
+    const MODULUS: u128 = 1_000_000_007;
+
+    fn standard_chain() -> RobotChain {
+        RobotChain::new(Keypad::numeric(), Keypad::directional()).unwrap()
+    }
+
+    #[test]
+    fn test_sequence_len_matches_game() {
+        let chain = standard_chain();
+
+        for depth in [0, 1, 2, 3, 10, 25] {
+            let mut game = Game::new(robot_chain(Keypad::numeric(), depth as usize)).unwrap();
+
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                assert_eq!(
+                    chain.sequence_len(code, depth).unwrap(),
+                    game.get_sequence_len(code).unwrap() as u128
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequence_len_mod() {
+        let chain = standard_chain();
+
+        for depth in [0, 1, 2, 3, 4, 5, 10, 25, 60] {
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                let exact = chain.sequence_len(code, depth).unwrap();
+
+                assert_eq!(
+                    chain.sequence_len_mod(code, depth, MODULUS).unwrap(),
+                    exact % MODULUS
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_repeated_choices_match_exact() {
+        let chain = standard_chain();
+
+        // Choices settle after four levels, and repeating them from there on agrees with
+        // choosing from exact costs at every depth
+        for code in ["029A", "980A", "179A", "456A", "379A"] {
+            for depth in [5, 10, 25, 60] {
+                let exact = chain.sequence_len(code, depth).unwrap();
+
+                for exact_levels in (4..depth).step_by(7) {
+                    assert_eq!(
+                        chain
+                            .sequence_len_mod_within(code, depth, MODULUS, exact_levels)
+                            .unwrap(),
+                        exact % MODULUS
+                    );
+                }
+            }
+        }
+
+        assert!(chain
+            .sequence_len_mod_within("029A", 10, MODULUS, 3)
+            .is_err());
+
+        // Other keypads are only counted from exact costs
+        let mirrored = Keypad::parse("A^.\n<v>", '.').unwrap();
+        let chain = RobotChain::new(Keypad::numeric(), mirrored).unwrap();
+        assert_eq!(
+            chain.sequence_len_mod("029A", 10, MODULUS).unwrap(),
+            chain.sequence_len("029A", 10).unwrap() % MODULUS
+        );
+        assert!(chain
+            .sequence_len_mod_within("029A", 10, MODULUS, 5)
+            .is_err());
+        assert!(chain.sequence_len_mod("029A", 1_000, MODULUS).is_err());
+    }
+
+    #[test]
+    fn test_large_depth() {
+        let chain = standard_chain();
+
+        assert!(chain.sequence_len("029A", 1_000).is_err());
+        assert!(chain.sequence_len_mod("029A", 1_000_000, MODULUS).unwrap() < MODULUS);
+        assert!(chain.sequence_len_mod("029A", 1_000_000, 0).is_err());
+    }
+
+    #[test]
+    fn test_complexity() {
+        let chain = standard_chain();
+
+        assert_eq!(
+            chain
+                .complexity(&["029A", "980A", "179A", "456A", "379A"], 2)
+                .unwrap(),
+            126384
+        );
+        assert_eq!(
+            chain
+                .complexity_mod(&["029A", "980A", "179A", "456A", "379A"], 2, 1_000)
+                .unwrap(),
+            384
+        );
+    }
+}
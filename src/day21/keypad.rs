@@ -0,0 +1,196 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    error::Error,
+    ops::Add,
+};
+
+use crate::util::{iter_2d, Direction, Point};
+
+pub const ACTIVATE: char = 'A';
+
+const NUMERIC_LAYOUT: &str = "789\n456\n123\n#0A";
+const DIRECTIONAL_LAYOUT: &str = "#^A\n<v>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keypad {
+    keys: HashMap<char, Point>,
+    positions: HashSet<Point>,
+    start: char,
+}
+
+impl Keypad {
+    pub fn parse(layout: &str, gap: char) -> Result<Self, Box<dyn Error>> {
+        let mut keys = HashMap::new();
+
+        for (pos, key) in iter_2d(layout.lines().map(|line| line.chars())) {
+            if key == gap {
+                continue;
+            }
+
+            if keys.insert(key, pos).is_some() {
+                return Err(format!("Duplicate key '{key}' in keypad layout").into());
+            }
+        }
+
+        if keys.is_empty() {
+            return Err("Keypad layout has no keys".into());
+        }
+
+        let positions = keys.values().copied().collect();
+
+        Ok(Self {
+            keys,
+            positions,
+            start: ACTIVATE,
+        })
+    }
+
+    pub fn numeric() -> Self {
+        Self::parse(NUMERIC_LAYOUT, '#').unwrap()
+    }
+
+    pub fn directional() -> Self {
+        Self::parse(DIRECTIONAL_LAYOUT, '#').unwrap()
+    }
+
+    // Key the arm rests on before the first press, for layouts without an activate key
+    pub fn with_start(self, start: char) -> Result<Self, Box<dyn Error>> {
+        if !self.keys.contains_key(&start) {
+            return Err(format!("Start key '{start}' is not on the keypad").into());
+        }
+
+        Ok(Self { start, ..self })
+    }
+
+    pub fn start(&self) -> char {
+        self.start
+    }
+
+    pub fn position(&self, key: char) -> Option<Point> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn contains_position(&self, pos: Point) -> bool {
+        self.positions.contains(&pos)
+    }
+
+    pub fn cheapest_sequence<T>(
+        &self,
+        from: char,
+        to: char,
+        cost: impl Fn(char, char) -> T,
+    ) -> Option<(T, Vec<char>)>
+    where
+        T: Copy + Ord + Default + Add<Output = T>,
+    {
+        let start = (self.position(from)?, ACTIVATE);
+        let target = self.position(to)?;
+
+        let mut best = HashMap::from([(start, T::default())]);
+        let mut came_from = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((T::default(), start))]);
+        let mut result: Option<(T, (Point, char))> = None;
+
+        while let Some(Reverse((score, state))) = queue.pop() {
+            if best.get(&state).is_some_and(|&s| s < score) {
+                continue;
+            }
+
+            let (pos, last_key) = state;
+
+            if pos == target {
+                let total = score + cost(last_key, ACTIVATE);
+
+                if result.is_none_or(|(r, _)| total < r) {
+                    result = Some((total, state));
+                }
+            }
+
+            for dir in Direction::all() {
+                let next_pos = pos + dir;
+
+                if !self.contains_position(next_pos) {
+                    continue;
+                }
+
+                let next_state = (next_pos, direction_key(dir));
+                let next_score = score + cost(last_key, next_state.1);
+
+                if best.get(&next_state).is_none_or(|&s| next_score < s) {
+                    best.insert(next_state, next_score);
+                    came_from.insert(next_state, state);
+                    queue.push(Reverse((next_score, next_state)));
+                }
+            }
+        }
+
+        let (total, mut state) = result?;
+
+        let mut keys = vec![ACTIVATE];
+        while let Some(&prev) = came_from.get(&state) {
+            keys.push(state.1);
+            state = prev;
+        }
+        keys.reverse();
+
+        Some((total, keys))
+    }
+
+    pub fn is_directional(&self) -> bool {
+        Direction::all()
+            .map(direction_key)
+            .chain(std::iter::once(ACTIVATE))
+            .all(|key| self.keys.contains_key(&key))
+            && self.start == ACTIVATE
+    }
+}
+
+pub fn direction_key(dir: Direction) -> char {
+    match dir {
+        Direction::Up => '^',
+        Direction::Right => '>',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let keypad = Keypad::numeric();
+
+        assert_eq!(keypad.position('7'), Some(Point(0, 0)));
+        assert_eq!(keypad.position('A'), Some(Point(2, 3)));
+        assert_eq!(keypad.position('#'), None);
+        assert!(!keypad.contains_position(Point(0, 3)));
+
+        assert!(Keypad::directional().is_directional());
+        assert!(!keypad.is_directional());
+    }
+
+    #[test]
+    fn test_cheapest_sequence() {
+        let keypad = Keypad::numeric();
+
+        assert_eq!(
+            keypad.cheapest_sequence('A', '7', |_, _| 1u64),
+            Some((6, vec!['^', '^', '^', '<', '<', 'A']))
+        );
+        assert_eq!(
+            keypad.cheapest_sequence('A', 'A', |_, _| 1u64),
+            Some((1, vec!['A']))
+        );
+        assert_eq!(keypad.cheapest_sequence('A', 'X', |_, _| 1u64), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Keypad::parse("12\n21", '.').is_err());
+        assert!(Keypad::parse("..\n..", '.').is_err());
+        assert!(Keypad::numeric().with_start('X').is_err());
+    }
+}
@@ -1,55 +1,104 @@
+mod chain;
 mod game;
+pub mod keypad;
+pub mod parse;
 
-use game::{Digit, Game};
+use chain::RobotChain;
+use game::Game;
+use keypad::Keypad;
 
 use crate::Puzzle;
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+#[derive(Default)]
+pub struct Part1 {
+    door: Option<Keypad>,
+}
+
+impl Part1 {
+    pub fn with_door(door: Option<Keypad>) -> Self {
+        Self { door }
+    }
+
+    fn door(&self) -> Keypad {
+        self.door.clone().unwrap_or_else(Keypad::numeric)
+    }
+}
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn std::error::Error>> {
-        get_complexity(INPUT, 3).map(|res| res.to_string())
+        get_complexity(INPUT, robot_chain(self.door(), 2)).map(|res| res.to_string())
     }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    door: Option<Keypad>,
+    robots: Option<u64>,
+    modulus: Option<u64>,
+}
+
+impl Part2 {
+    pub fn with_robots(door: Option<Keypad>, robots: Option<u64>, modulus: Option<u64>) -> Self {
+        Self {
+            door,
+            robots,
+            modulus,
+        }
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u128, Box<dyn std::error::Error>> {
+        let door = self.door.clone().unwrap_or_else(Keypad::numeric);
+        let chain = RobotChain::new(door, Keypad::directional())?;
+        let codes = parse_input(input);
+        let depth = self.robots.unwrap_or(25);
+
+        match self.modulus {
+            Some(modulus) => chain.complexity_mod(&codes, depth, modulus as u128),
+            None => chain.complexity(&codes, depth),
+        }
+    }
+}
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn std::error::Error>> {
-        get_complexity(INPUT, 26).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
     }
 }
 
-fn parse_input(input: &str) -> Vec<Vec<Digit>> {
+fn parse_input(input: &str) -> Vec<&str> {
     input
         .lines()
-        .map(|line| {
-            line.chars()
-                .filter_map(Digit::from_char)
-                .collect()
-        })
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn robot_chain(door: Keypad, directional_count: usize) -> Vec<Keypad> {
+    std::iter::once(door)
+        .chain(std::iter::repeat_n(
+            Keypad::directional(),
+            directional_count,
+        ))
         .collect()
 }
 
-fn get_complexity(input: &str, max_level: u64) -> Result<u64, Box<dyn std::error::Error>> {
-    let mut game = Game::new(max_level);
+fn get_complexity(input: &str, keypads: Vec<Keypad>) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut game = Game::new(keypads)?;
 
     let codes = parse_input(input);
 
-    let complexity = codes
+    codes
         .into_iter()
         .map(|code| {
-            let sequence_len = game.get_sequence_len(code.iter().copied());
+            let sequence_len = game.get_sequence_len(code)?;
 
             let num = Game::get_numeric_part(code);
 
-            num * sequence_len
+            Ok(num * sequence_len)
         })
-        .sum::<u64>();
-
-    Ok(complexity)
+        .sum()
 }
 
 #[cfg(test)]
@@ -60,8 +109,37 @@ mod tests {
 
     #[test]
     fn test_get_complexity() {
-        let complexity = get_complexity(TEST_INPUT, 3).unwrap();
+        let complexity = get_complexity(TEST_INPUT, robot_chain(Keypad::numeric(), 2)).unwrap();
 
         assert_eq!(complexity, 126384);
+
+        // Same keys with the top and bottom rows swapped
+        let door = parse::parse_keypad("#0A/123/456/789").unwrap();
+        let part = Part1::with_door(Some(door.clone()));
+        assert_ne!(
+            get_complexity(TEST_INPUT, robot_chain(part.door(), 2)).unwrap(),
+            126384
+        );
+
+        let part = Part2::with_robots(Some(door), Some(2), None);
+        assert_eq!(
+            part.solve_input(TEST_INPUT).unwrap(),
+            get_complexity(TEST_INPUT, robot_chain(part.door.clone().unwrap(), 2)).unwrap() as u128
+        );
+    }
+
+    #[test]
+    fn test_part2_settings() {
+        let part = Part2::with_robots(None, Some(2), None);
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 126384);
+
+        let part = Part2::with_robots(None, Some(2), Some(1_000));
+        assert_eq!(part.solve_input(TEST_INPUT).unwrap(), 384);
+
+        let part = Part2::with_robots(None, Some(1_000), None);
+        assert!(part.solve_input(TEST_INPUT).is_err());
+
+        let part = Part2::with_robots(None, Some(1_000), Some(1_000_000_007));
+        assert!(part.solve_input(TEST_INPUT).unwrap() < 1_000_000_007);
     }
 }
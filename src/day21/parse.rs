@@ -0,0 +1,41 @@
+use nom::{
+    bytes::complete::is_not,
+    character::complete::{anychar, char},
+    combinator::{all_consuming, opt},
+    sequence::{pair, preceded},
+};
+
+use super::keypad::Keypad;
+
+pub fn parse_keypad(input: &str) -> Result<Keypad, String> {
+    // "789/456/123/#0A", rows split by '/', '#' for gaps and an optional "@KEY" start
+    let (_, (layout, start)) = all_consuming(pair(
+        is_not::<_, _, nom::error::Error<_>>("@"),
+        opt(preceded(char('@'), anychar)),
+    ))(input)
+    .map_err(|_| format!("Invalid keypad '{input}', expected ROWS[@START]"))?;
+
+    let keypad = Keypad::parse(&layout.replace('/', "\n"), '#').map_err(|e| e.to_string())?;
+
+    match start {
+        Some(start) => keypad.with_start(start).map_err(|e| e.to_string()),
+        None => Ok(keypad),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keypad() {
+        assert_eq!(parse_keypad("789/456/123/#0A"), Ok(Keypad::numeric()));
+
+        let keypad = parse_keypad("123/456/789/#0#@0").unwrap();
+        assert_eq!(keypad.start(), '0');
+
+        assert!(parse_keypad("123@X").is_err());
+        assert!(parse_keypad("11").is_err());
+        assert!(parse_keypad("@1").is_err());
+    }
+}
@@ -48,7 +48,6 @@ fn next_secret_number(secret_number: u64) -> u64 {
     let secret_number = prune(secret_number);
 
     let secret_number = mix(secret_number, secret_number * 2048);
-    
 
     prune(secret_number)
 }
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+
+use crate::util::Point;
+
+use super::rules::Guard;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cycle {
+    // Every state from the starting guard up to the end of the first lap
+    pub path: Vec<Guard>,
+    pub entry_index: usize,
+}
+
+impl Cycle {
+    pub fn entry(&self) -> Guard {
+        self.path[self.entry_index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.path.len() - self.entry_index
+    }
+
+    pub fn states(&self) -> &[Guard] {
+        &self.path[self.entry_index..]
+    }
+
+    pub fn cells(&self) -> HashSet<Point> {
+        self.states().iter().map(|guard| guard.pos).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Direction;
+
+    #[test]
+    fn test_cycle() {
+        let path = [
+            Point(0, 0),
+            Point(1, 0),
+            Point(1, 1),
+            Point(0, 1),
+            Point(0, 0),
+        ]
+        .map(|pos| Guard::new(pos, Direction::Up))
+        .to_vec();
+
+        let cycle = Cycle {
+            path,
+            entry_index: 1,
+        };
+
+        assert_eq!(cycle.entry(), Guard::new(Point(1, 0), Direction::Up));
+        assert_eq!(cycle.len(), 4);
+        assert_eq!(cycle.states().len(), 4);
+        assert_eq!(cycle.cells().len(), 4);
+    }
+}
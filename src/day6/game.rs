@@ -1,18 +1,52 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::util::{Direction, Point, PointRange};
 
+use super::{
+    cycle::Cycle,
+    jump::{JumpTable, Walk},
+    rules::{Boundary, Guard, Heading, Rules},
+};
+
 #[derive(Debug, Clone)]
 pub struct Game {
     walls: HashSet<Point>,
     range: PointRange,
+    rules: Rules,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Step {
+    Moved(Guard),
+    Exited,
+    Stuck,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Outcome {
+    Exited,
+    Loop,
+    Collision { tick: u64, positions: Vec<Point> },
 }
 
 impl Game {
     pub fn new(walls: HashSet<Point>, range: PointRange) -> Game {
-        Game { walls, range }
+        Self::with_rules(walls, range, Rules::default())
+    }
+
+    pub fn with_rules(walls: HashSet<Point>, range: PointRange, rules: Rules) -> Game {
+        Game {
+            walls,
+            range,
+            rules,
+        }
     }
 
+    pub fn jump_table(&self) -> JumpTable {
+        JumpTable::new(&self.walls, &self.range)
+    }
+
+    #[cfg(test)]
     pub fn clone_with_obstacle(&self, obstacle: Point) -> Self {
         let mut game = self.clone();
         game.walls.insert(obstacle);
@@ -20,34 +54,515 @@ impl Game {
         game
     }
 
-    pub fn iter(&self, guard: (Point, Direction)) -> impl Iterator<Item = (Point, Direction)> + '_ {
-        let (mut pos, mut dir) = guard;
+    fn step(&self, guard: Guard) -> Step {
+        self.step_with(guard, None)
+    }
 
-        std::iter::once(guard).chain(std::iter::from_fn(move || loop {
-            let new_pos = pos + dir;
+    // Step with an extra wall at `obstacle`, without copying the map
+    fn step_with(&self, guard: Guard, obstacle: Option<Point>) -> Step {
+        for steps in 0..self.rules.heading_count() as u8 {
+            let mut guard = match steps {
+                0 => guard,
+                _ => self.rules.turn(guard, steps),
+            };
+            let mut new_pos = guard.pos + guard.heading.offset();
 
             if !self.range.contains(new_pos) {
-                return None;
+                match self.rules.boundary {
+                    Boundary::Exit => return Step::Exited,
+                    Boundary::Wrap => {
+                        let start = self.range.start();
+                        new_pos = (new_pos - start).wrap(self.range.end() - start) + start;
+                    }
+                }
             }
 
-            if !self.walls.contains(&new_pos) {
-                pos = new_pos;
-                return Some((pos, dir));
+            if !self.walls.contains(&new_pos) && Some(new_pos) != obstacle {
+                guard.pos = new_pos;
+                return Step::Moved(guard);
             }
+        }
+
+        Step::Stuck
+    }
+
+    pub fn iter(&self, guard: Guard) -> impl Iterator<Item = Guard> + '_ {
+        let mut current = guard;
 
-            dir = dir.rotate_clockwise();
+        std::iter::once(guard).chain(std::iter::from_fn(move || match self.step(current) {
+            Step::Moved(guard) => {
+                current = guard;
+                Some(guard)
+            }
+            Step::Exited | Step::Stuck => None,
         }))
     }
 
-    pub fn is_loop(&self, guard: (Point, Direction)) -> bool {
+    // Whether the guard keeps walking forever; a walled-in guard stands still instead
+    pub fn is_loop(&self, guard: Guard) -> bool {
+        let mut visited_state = HashSet::from([guard]);
+        let mut current = guard;
+
+        loop {
+            match self.step(current) {
+                Step::Moved(guard) => {
+                    if !visited_state.insert(guard) {
+                        return true;
+                    }
+
+                    current = guard;
+                }
+                Step::Exited | Step::Stuck => return false,
+            }
+        }
+    }
+
+    // Cycle the guard ends up in with an extra wall at `obstacle`, or None if it leaves the
+    // map or is walled in
+    pub fn find_cycle(&self, guard: Guard, obstacle: Option<Point>) -> Option<Cycle> {
+        let mut path = vec![guard];
+        let mut indices = HashMap::from([(guard, 0)]);
+
+        loop {
+            match self.step_with(path[path.len() - 1], obstacle) {
+                Step::Moved(guard) => {
+                    if let Some(&entry_index) = indices.get(&guard) {
+                        return Some(Cycle { path, entry_index });
+                    }
+
+                    indices.insert(guard, path.len());
+                    path.push(guard);
+                }
+                Step::Exited | Step::Stuck => return None,
+            }
+        }
+    }
+
+    // Cells on the patrol path where an obstruction sends the guard into a cycle, in the
+    // order the guard first reaches them. Candidates are checked in parallel, and through the
+    // jump table from the state just before the obstruction when the rules are the puzzle's own
+    pub fn looping_obstructions(&self, guard: Guard) -> Vec<Point> {
+        let mut candidates = HashSet::from([guard.pos]);
+
+        let path = self.iter(guard).collect::<Vec<_>>();
+
+        let obstructions = path
+            .windows(2)
+            .map(|w| (w[0], w[1].pos))
+            .filter(|&(_, pos)| candidates.insert(pos))
+            .collect::<Vec<_>>();
+
+        if self.rules != Rules::default() {
+            return parallel_filter_map(&obstructions, |&(_, obstruction)| {
+                self.find_cycle(guard, Some(obstruction))
+                    .map(|_| obstruction)
+            });
+        }
+
+        let jump_table = self.jump_table();
+
+        parallel_filter_map(&obstructions, |&(before, obstruction)| {
+            let direction = before.heading.to_direction()?;
+
+            (jump_table.walk((before.pos, direction), Some(obstruction)) == Walk::Loops)
+                .then_some(obstruction)
+        })
+    }
+
+    pub fn render(&self, path: &[Guard], obstruction: Option<Point>) -> String {
+        let mut marks: HashMap<Point, HashSet<char>> = HashMap::new();
+
+        for (i, guard) in path.iter().enumerate() {
+            let cell = marks.entry(guard.pos).or_default();
+            cell.insert(line_char(guard.heading));
+
+            // The guard turns in place, so the cell also carries its leaving heading
+            if let Some(next) = path.get(i + 1) {
+                cell.insert(line_char(next.heading));
+            }
+        }
+
+        let start = path.first();
+        let Point(start_x, start_y) = self.range.start();
+        let Point(end_x, end_y) = self.range.end();
+
+        (start_y..end_y)
+            .map(|y| {
+                (start_x..end_x)
+                    .map(|x| {
+                        let pos = Point(x, y);
+
+                        if Some(pos) == obstruction {
+                            'O'
+                        } else if self.walls.contains(&pos) {
+                            '#'
+                        } else if let Some(guard) = start.filter(|g| g.pos == pos) {
+                            guard_char(guard.heading)
+                        } else {
+                            match marks.get(&pos) {
+                                Some(chars) if chars.len() > 1 => '+',
+                                Some(chars) => *chars.iter().next().unwrap(),
+                                None => '.',
+                            }
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn iter_guards(&self, guards: Vec<Guard>) -> impl Iterator<Item = Vec<Option<Guard>>> + '_ {
+        let mut current = guards.into_iter().map(Some).collect::<Vec<_>>();
+        let mut finished = false;
+
+        std::iter::once(current.clone()).chain(std::iter::from_fn(move || {
+            if finished || current.iter().all(|g| g.is_none()) {
+                return None;
+            }
+
+            let next = current
+                .iter()
+                .map(|guard| match guard.map(|g| self.step(g)) {
+                    Some(Step::Moved(g)) => Some(g),
+                    Some(Step::Stuck) => *guard,
+                    Some(Step::Exited) | None => None,
+                })
+                .collect::<Vec<_>>();
+
+            finished = !Self::collisions(&current, &next).is_empty();
+            current = next;
+
+            Some(current.clone())
+        }))
+    }
+
+    pub fn run_guards(&self, guards: Vec<Guard>) -> Outcome {
         let mut visited_state = HashSet::new();
+        let mut previous: Option<Vec<Option<Guard>>> = None;
+
+        for (tick, state) in self.iter_guards(guards).enumerate() {
+            if let Some(previous) = &previous {
+                let positions = Self::collisions(previous, &state);
+
+                if !positions.is_empty() {
+                    return Outcome::Collision {
+                        tick: tick as u64,
+                        positions,
+                    };
+                }
+            }
+
+            if !visited_state.insert(state.clone()) {
+                return Outcome::Loop;
+            }
+
+            previous = Some(state);
+        }
 
-        for state in self.iter(guard) {
-            if !visited_state.insert(state) {
-                return true;
+        Outcome::Exited
+    }
+
+    // Cells where two guards meet or swap places during a single tick
+    fn collisions(previous: &[Option<Guard>], next: &[Option<Guard>]) -> Vec<Point> {
+        let mut occupied: HashMap<Point, usize> = HashMap::new();
+
+        for guard in next.iter().flatten() {
+            *occupied.entry(guard.pos).or_default() += 1;
+        }
+
+        let mut positions = occupied
+            .into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|(pos, _)| pos)
+            .collect::<HashSet<_>>();
+
+        for (i, (prev_a, next_a)) in previous.iter().zip(next).enumerate() {
+            for (prev_b, next_b) in previous.iter().zip(next).skip(i + 1) {
+                if let (Some(pa), Some(na), Some(pb), Some(nb)) = (prev_a, next_a, prev_b, next_b) {
+                    if pa.pos == nb.pos && pb.pos == na.pos && pa.pos != na.pos {
+                        positions.insert(na.pos);
+                        positions.insert(nb.pos);
+                    }
+                }
             }
         }
 
-        false
+        let mut positions = positions.into_iter().collect::<Vec<_>>();
+        positions.sort();
+        positions
+    }
+}
+
+// `f` applied to every item across all available threads, keeping the order of `items`
+fn parallel_filter_map<T: Sync, U: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> Option<U> + Sync,
+) -> Vec<U> {
+    let thread_count = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = items.len().div_ceil(thread_count).max(1);
+    let f = &f;
+
+    std::thread::scope(|scope| {
+        items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().filter_map(f).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+fn line_char(heading: Heading) -> char {
+    match heading.offset() {
+        Point(0, _) => '|',
+        Point(_, 0) => '-',
+        Point(x, y) if x == y => '\\',
+        _ => '/',
+    }
+}
+
+fn guard_char(heading: Heading) -> char {
+    match heading.to_direction() {
+        Some(Direction::Up) => '^',
+        Some(Direction::Right) => '>',
+        Some(Direction::Down) => 'v',
+        Some(Direction::Left) => '<',
+        None => line_char(heading),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        parse::parse_input,
+        rules::{Headings, Turn},
+    };
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test-input.txt");
+
+    fn game_with_rules(rules: Rules) -> (Game, Point) {
+        let (_, (walls, range, guard_pos)) = parse_input(TEST_INPUT).unwrap();
+        (Game::with_rules(walls, range, rules), guard_pos)
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let (game, guard_pos) = game_with_rules(Rules::default());
+        let guard = Guard::new(guard_pos, Direction::Up);
+
+        let visited = game.iter(guard).map(|g| g.pos).collect::<HashSet<_>>();
+
+        assert_eq!(visited.len(), 41);
+        assert!(!game.is_loop(guard));
+        assert!(game.clone_with_obstacle(Point(3, 6)).is_loop(guard));
+    }
+
+    #[test]
+    fn test_alternative_rules() {
+        let (game, guard_pos) = game_with_rules(Rules {
+            turn: Turn::Left,
+            ..Rules::default()
+        });
+        let guard = Guard::new(guard_pos, Direction::Up);
+        let path = game.iter(guard).collect::<Vec<_>>();
+        assert_eq!(path[5].heading, Direction::Up.into());
+        assert_eq!(path[6].heading, Direction::Left.into());
+        assert!(!game.is_loop(guard));
+
+        let (game, _) = game_with_rules(Rules {
+            boundary: Boundary::Wrap,
+            ..Rules::default()
+        });
+        assert!(game.is_loop(guard));
+        assert_eq!(game.iter(guard).take(500).count(), 500);
+
+        let (game, _) = game_with_rules(Rules {
+            turn: Turn::Alternate,
+            headings: Headings::Diagonal,
+            boundary: Boundary::Exit,
+        });
+        let path = game.iter(guard).collect::<Vec<_>>();
+        assert!(path.len() > 1);
+        assert!(path.iter().any(|g| g.heading.to_direction().is_none()));
+        assert!(!game.is_loop(guard));
+    }
+
+    #[test]
+    fn test_stuck_guard() {
+        let walls = HashSet::from([Point(1, 0), Point(0, 1), Point(2, 1), Point(1, 2)]);
+        let game = Game::new(walls.clone(), PointRange::new(Point(0, 0), Point(3, 3)));
+        let guard = Guard::new(Point(1, 1), Direction::Up);
+
+        assert_eq!(game.iter(guard).count(), 1);
+        assert!(!game.is_loop(guard));
+        assert_eq!(game.find_cycle(guard, None), None);
+
+        // A single way out is found whichever side the guard turns to first
+        for turn in [Turn::Right, Turn::Left, Turn::Alternate] {
+            for open in [Point(1, 0), Point(0, 1), Point(2, 1), Point(1, 2)] {
+                let mut walls = walls.clone();
+                walls.remove(&open);
+
+                let rules = Rules {
+                    turn,
+                    ..Rules::default()
+                };
+                let game =
+                    Game::with_rules(walls, PointRange::new(Point(0, 0), Point(3, 3)), rules);
+
+                assert_eq!(game.iter(guard).nth(1).unwrap().pos, open);
+                assert!(!game.is_loop(guard));
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiple_guards() {
+        let game = Game::new(HashSet::new(), PointRange::new(Point(0, 0), Point(5, 1)));
+
+        let meeting = vec![
+            Guard::new(Point(0, 0), Direction::Right),
+            Guard::new(Point(4, 0), Direction::Left),
+        ];
+        assert_eq!(
+            game.run_guards(meeting.clone()),
+            Outcome::Collision {
+                tick: 2,
+                positions: vec![Point(2, 0)]
+            }
+        );
+        assert_eq!(game.iter_guards(meeting).count(), 3);
+
+        let swapping = vec![
+            Guard::new(Point(1, 0), Direction::Right),
+            Guard::new(Point(2, 0), Direction::Left),
+        ];
+        assert_eq!(
+            game.run_guards(swapping),
+            Outcome::Collision {
+                tick: 1,
+                positions: vec![Point(1, 0), Point(2, 0)]
+            }
+        );
+
+        let parallel = vec![
+            Guard::new(Point(0, 0), Direction::Right),
+            Guard::new(Point(1, 0), Direction::Right),
+        ];
+        assert_eq!(game.run_guards(parallel), Outcome::Exited);
+
+        let game = Game::with_rules(
+            HashSet::new(),
+            PointRange::new(Point(0, 0), Point(5, 2)),
+            Rules {
+                boundary: Boundary::Wrap,
+                ..Rules::default()
+            },
+        );
+        let looping = vec![
+            Guard::new(Point(0, 0), Direction::Right),
+            Guard::new(Point(0, 1), Direction::Right),
+        ];
+        assert_eq!(game.run_guards(looping), Outcome::Loop);
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let (game, guard_pos) = game_with_rules(Rules::default());
+        let guard = Guard::new(guard_pos, Direction::Up);
+
+        assert_eq!(game.find_cycle(guard, None), None);
+
+        let cycle = game.find_cycle(guard, Some(Point(3, 6))).unwrap();
+        assert_eq!(
+            game.clone_with_obstacle(Point(3, 6))
+                .find_cycle(guard, None),
+            Some(cycle.clone())
+        );
+        assert_eq!(cycle.entry(), Guard::new(Point(4, 5), Direction::Up));
+        assert_eq!(cycle.len(), 18);
+        assert_eq!(cycle.cells().len(), 18);
+    }
+
+    #[test]
+    fn test_looping_obstructions() {
+        let (game, guard_pos) = game_with_rules(Rules::default());
+        let guard = Guard::new(guard_pos, Direction::Up);
+
+        let mut obstructions = game.looping_obstructions(guard);
+        obstructions.sort();
+
+        assert_eq!(
+            obstructions,
+            vec![
+                Point(1, 8),
+                Point(3, 6),
+                Point(3, 8),
+                Point(6, 7),
+                Point(7, 7),
+                Point(7, 9)
+            ]
+        );
+
+        // Matches stepping through a copy of the map with each obstruction added
+        for rules in [
+            Rules::default(),
+            Rules {
+                turn: Turn::Left,
+                ..Rules::default()
+            },
+        ] {
+            let (game, _) = game_with_rules(rules);
+            let looping = game.looping_obstructions(guard);
+
+            let expected = game
+                .iter(guard)
+                .map(|g| g.pos)
+                .filter(|&pos| pos != guard.pos)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter(|&pos| game.clone_with_obstacle(pos).is_loop(guard))
+                .collect::<Vec<_>>();
+
+            assert_eq!(looping.len(), expected.len());
+            assert!(looping.iter().all(|item| expected.contains(item)));
+        }
+    }
+
+    #[test]
+    fn test_stuck_obstruction() {
+        // Blocking the corridor ahead walls the guard in, which is not a loop
+        let walls = HashSet::from([Point(0, 1), Point(2, 1), Point(1, 2)]);
+        let game = Game::new(walls, PointRange::new(Point(0, 0), Point(3, 3)));
+
+        assert!(game
+            .looping_obstructions(Guard::new(Point(1, 1), Direction::Up))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let (game, guard_pos) = game_with_rules(Rules::default());
+        let guard = Guard::new(guard_pos, Direction::Up);
+        let obstruction = Point(3, 6);
+
+        let cycle = game.find_cycle(guard, Some(obstruction)).unwrap();
+
+        assert_eq!(
+            game.render(&cycle.path, Some(obstruction)),
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#..."
+        );
     }
 }
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::util::{Direction, Point, PointRange};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Walk {
+    Exits,
+    Loops,
+    // Walled in on every side, so the guard only turns in place
+    Stuck,
+}
+
+pub struct JumpTable {
+    stops: HashMap<(Point, Direction), Option<Point>>,
+}
+
+impl JumpTable {
+    pub fn new(walls: &HashSet<Point>, range: &PointRange) -> Self {
+        let mut stops = HashMap::new();
+
+        for direction in Direction::all() {
+            let step = Point(0, 0) + direction;
+
+            let mut points = range
+                .points()
+                .filter(|point| !walls.contains(point))
+                .collect::<Vec<_>>();
+
+            points.sort_by_key(|&Point(x, y)| -(x * step.0 + y * step.1));
+
+            for point in points {
+                let next = point + direction;
+
+                let stop = if walls.contains(&next) {
+                    Some(point)
+                } else if range.contains(next) {
+                    stops[&(next, direction)]
+                } else {
+                    None
+                };
+
+                stops.insert((point, direction), stop);
+            }
+        }
+
+        Self { stops }
+    }
+
+    // Position where the guard stops before turning, or None if it leaves the map
+    pub fn next_stop(
+        &self,
+        pos: Point,
+        direction: Direction,
+        obstacle: Option<Point>,
+    ) -> Option<Point> {
+        let stop = self.stops[&(pos, direction)];
+
+        let Some(obstacle) = obstacle else {
+            return stop;
+        };
+
+        let offset = obstacle - pos;
+        let step = Point(0, 0) + direction;
+        let distance = offset.0 * step.0 + offset.1 * step.1;
+
+        let is_ahead = distance > 0 && Point(step.0 * distance, step.1 * distance) == offset;
+        let is_before_stop = stop.is_none_or(|stop| distance <= stop.distance(pos) as i64);
+
+        if is_ahead && is_before_stop {
+            Some(obstacle - step)
+        } else {
+            stop
+        }
+    }
+
+    pub fn walk(&self, guard: (Point, Direction), obstacle: Option<Point>) -> Walk {
+        let (mut pos, mut direction) = guard;
+        let mut visited_stops = HashSet::new();
+        let mut turns_in_place = 0;
+
+        while let Some(stop) = self.next_stop(pos, direction, obstacle) {
+            turns_in_place = if stop == pos { turns_in_place + 1 } else { 0 };
+
+            if turns_in_place == Direction::all().count() {
+                return Walk::Stuck;
+            }
+
+            if !visited_stops.insert((stop, direction)) {
+                return Walk::Loops;
+            }
+
+            pos = stop;
+            direction = direction.rotate_clockwise();
+        }
+
+        Walk::Exits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{game::Game, parse::parse_input, rules::Guard};
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test-input.txt");
+
+    #[test]
+    fn test_next_stop() {
+        let (_, (walls, range, guard_pos)) = parse_input(TEST_INPUT).unwrap();
+        let table = JumpTable::new(&walls, &range);
+
+        assert_eq!(
+            table.next_stop(guard_pos, Direction::Up, None),
+            Some(Point(4, 1))
+        );
+        assert_eq!(
+            table.next_stop(Point(4, 1), Direction::Right, None),
+            Some(Point(8, 1))
+        );
+        assert_eq!(table.next_stop(Point(7, 7), Direction::Down, None), None);
+        assert_eq!(
+            table.next_stop(guard_pos, Direction::Up, Some(Point(4, 3))),
+            Some(Point(4, 4))
+        );
+        assert_eq!(
+            table.next_stop(guard_pos, Direction::Up, Some(Point(4, 8))),
+            Some(Point(4, 1))
+        );
+    }
+
+    #[test]
+    fn test_walk() {
+        let (_, (walls, range, guard_pos)) = parse_input(TEST_INPUT).unwrap();
+        let table = JumpTable::new(&walls, &range);
+        let game = Game::new(walls.clone(), range);
+
+        let guard = (guard_pos, Direction::Up);
+
+        assert_eq!(table.walk(guard, None), Walk::Exits);
+        assert_eq!(table.walk(guard, Some(Point(3, 6))), Walk::Loops);
+
+        for x in 0..10 {
+            for y in 0..10 {
+                let obstacle = Point(x, y);
+
+                if obstacle == guard_pos || walls.contains(&obstacle) {
+                    continue;
+                }
+
+                assert_eq!(
+                    table.walk(guard, Some(obstacle)) == Walk::Loops,
+                    game.clone_with_obstacle(obstacle)
+                        .is_loop(Guard::new(guard_pos, Direction::Up))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_stuck() {
+        let walls = HashSet::from([Point(1, 0), Point(0, 1), Point(2, 1), Point(1, 2)]);
+        let table = JumpTable::new(&walls, &PointRange::new(Point(0, 0), Point(3, 3)));
+
+        for direction in Direction::all() {
+            assert_eq!(table.walk((Point(1, 1), direction), None), Walk::Stuck);
+        }
+
+        // The obstacle closes the last way out
+        let walls = HashSet::from([Point(0, 1), Point(2, 1), Point(1, 2)]);
+        let table = JumpTable::new(&walls, &PointRange::new(Point(0, 0), Point(3, 3)));
+
+        assert_eq!(table.walk((Point(1, 1), Direction::Up), None), Walk::Exits);
+        assert_eq!(
+            table.walk((Point(1, 1), Direction::Up), Some(Point(1, 0))),
+            Walk::Stuck
+        );
+    }
+}
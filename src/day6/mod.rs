@@ -1,34 +1,99 @@
+mod cycle;
 mod game;
-mod parse;
+mod jump;
+pub mod parse;
+pub mod rules;
 
 use std::{collections::HashSet, error::Error};
 
-use game::Game;
+use game::{Game, Outcome};
 use parse::parse_input;
+use rules::{Boundary, Guard, Headings, Rules, Turn};
 
-use crate::{util::Direction, Puzzle};
+use crate::{
+    util::{Direction, Point},
+    Puzzle,
+};
 
 const INPUT: &str = include_str!("input.txt");
 
-pub struct Part1;
+#[derive(Default)]
+pub struct Part1 {
+    rules: Rules,
+    extra_guards: Vec<Guard>,
+}
 
 impl Part1 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
+    pub fn with_rules(
+        turn: Turn,
+        boundary: Boundary,
+        headings: Headings,
+        extra_guards: Vec<Guard>,
+    ) -> Self {
+        Self {
+            rules: Rules {
+                turn,
+                boundary,
+                headings,
+            },
+            extra_guards,
+        }
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
         let (_, (walls, range, guard_pos)) = parse_input(input).map_err(|e| e.to_owned())?;
 
-        let game = Game::new(walls, range);
+        let game = Game::with_rules(walls, range, self.rules);
+
+        // Stop after the first lap when the guard loops
+        let mut seen = HashSet::new();
 
         Ok(game
-            .iter((guard_pos, Direction::Up))
-            .map(|(pos, _)| pos)
+            .iter(Guard::new(guard_pos, Direction::Up))
+            .take_while(|&guard| seen.insert(guard))
+            .map(|guard| guard.pos)
             .collect::<HashSet<_>>()
             .len() as u64)
     }
+
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let (_, (walls, range, guard_pos)) = parse_input(input).map_err(|e| e.to_owned())?;
+
+        let game = Game::with_rules(walls, range, self.rules);
+        let guard = Guard::new(guard_pos, Direction::Up);
+
+        let mut report = format!("guard loops: {}", game.is_loop(guard));
+
+        if !self.extra_guards.is_empty() {
+            let guards = std::iter::once(guard)
+                .chain(self.extra_guards.iter().copied())
+                .collect();
+
+            report += &match game.run_guards(guards) {
+                Outcome::Exited => "\nall guards leave the map".to_string(),
+                Outcome::Loop => "\nguards loop without colliding".to_string(),
+                Outcome::Collision { tick, positions } => format!(
+                    "\nguards collide at tick {tick} on {}",
+                    positions
+                        .iter()
+                        .map(|Point(x, y)| format!("{x},{y}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
+            };
+        }
+
+        Ok(report)
+    }
 }
 
 impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
     }
 }
 
@@ -40,24 +105,55 @@ impl Part2 {
 
         let game = Game::new(walls, range);
 
-        let guard = (guard_pos, Direction::Up);
+        Ok(game
+            .looping_obstructions(Guard::new(guard_pos, Direction::Up))
+            .len() as u64)
+    }
 
-        let positions = game.iter(guard).collect::<Vec<_>>();
+    // Every looping obstruction with the cycle it causes
+    fn report_input(input: &str) -> Result<String, Box<dyn Error>> {
+        let (_, (walls, range, guard_pos)) = parse_input(input).map_err(|e| e.to_owned())?;
 
-        let mut visited = HashSet::new();
+        let game = Game::new(walls, range);
+        let guard = Guard::new(guard_pos, Direction::Up);
 
-        let obstructions = positions
-            .windows(2)
-            .map(|w| (w[0], w[1].0))
-            .filter(|&(_, pos)| visited.insert(pos));
+        Ok(game
+            .looping_obstructions(guard)
+            .into_iter()
+            .filter_map(|obstruction| {
+                game.find_cycle(guard, Some(obstruction))
+                    .map(|cycle| (obstruction, cycle))
+            })
+            .map(|(Point(x, y), cycle)| {
+                let entry = cycle.entry();
+
+                format!(
+                    "{x},{y}: enters at {},{} heading {:?}, {} states over {} cells",
+                    entry.pos.0,
+                    entry.pos.1,
+                    entry.heading.to_direction().unwrap(),
+                    cycle.len(),
+                    cycle.cells().len()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
 
-        Ok(obstructions
-            .filter(|(guard, obstruction_pos)| {
-                let modified_game = game.clone_with_obstacle(*obstruction_pos);
+    // The cycle caused by the first looping obstruction the guard walks past
+    fn image_input(input: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let (_, (walls, range, guard_pos)) = parse_input(input).map_err(|e| e.to_owned())?;
 
-                modified_game.is_loop(*guard)
-            })
-            .count() as u64)
+        let game = Game::new(walls, range);
+        let guard = Guard::new(guard_pos, Direction::Up);
+
+        Ok(game
+            .looping_obstructions(guard)
+            .first()
+            .and_then(|&obstruction| {
+                game.find_cycle(guard, Some(obstruction))
+                    .map(|cycle| game.render(&cycle.path, Some(obstruction)))
+            }))
     }
 }
 
@@ -65,6 +161,14 @@ impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
         Self::solve_input(INPUT).map(|res| res.to_string())
     }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Self::report_input(INPUT).map(Some)
+    }
+
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Self::image_input(INPUT)
+    }
 }
 
 #[cfg(test)]
@@ -75,11 +179,41 @@ mod tests {
 
     #[test]
     fn test_part1_solve_input() {
-        assert_eq!(Part1::solve_input(TEST_INPUT).unwrap(), 41);
+        assert_eq!(Part1::default().solve_input(TEST_INPUT).unwrap(), 41);
+        assert_eq!(
+            Part1::default().report_input(TEST_INPUT).unwrap(),
+            "guard loops: false"
+        );
+
+        let part = Part1::with_rules(Turn::Right, Boundary::Wrap, Headings::Orthogonal, vec![]);
+        assert_eq!(part.report_input(TEST_INPUT).unwrap(), "guard loops: true");
+        assert!(part.solve_input(TEST_INPUT).unwrap() > 41);
+
+        // The guard starts at 4,6 facing up
+        let part = Part1::with_rules(
+            Turn::Right,
+            Boundary::Exit,
+            Headings::Orthogonal,
+            vec![Guard::new(Point(4, 2), Direction::Down)],
+        );
+        assert_eq!(
+            part.report_input(TEST_INPUT).unwrap(),
+            "guard loops: false\nguards collide at tick 2 on 4,4"
+        );
     }
 
     #[test]
     fn test_part2_solve_input() {
         assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 6);
+
+        let report = Part2::report_input(TEST_INPUT).unwrap();
+        assert_eq!(report.lines().count(), 6);
+        assert_eq!(
+            report.lines().next().unwrap(),
+            "3,6: enters at 4,5 heading Up, 18 states over 18 cells"
+        );
+
+        let image = Part2::image_input(TEST_INPUT).unwrap().unwrap();
+        assert_eq!(image.lines().nth(6).unwrap(), ".#.O^---+.");
     }
 }
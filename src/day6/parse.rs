@@ -3,13 +3,16 @@ use std::collections::HashSet;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::newline,
-    combinator::map,
+    character::complete::{i64, newline},
+    combinator::{all_consuming, map},
     multi::{many1, separated_list1},
+    sequence::{separated_pair, tuple},
     IResult,
 };
 
-use crate::util::{iter_2d, Point, PointRange};
+use crate::util::{iter_2d, Direction, Point, PointRange};
+
+use super::rules::Guard;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Tile {
@@ -39,6 +42,22 @@ pub fn parse_input(input: &str) -> IResult<&str, (HashSet<Point>, PointRange, Po
     })(input)
 }
 
+pub fn parse_guard(input: &str) -> Result<Guard, String> {
+    // "3,4,>", as given on the command line
+    all_consuming(tuple((
+        separated_pair(i64::<_, nom::error::Error<_>>, tag(","), i64),
+        tag(","),
+        alt((
+            map(tag("^"), |_| Direction::Up),
+            map(tag(">"), |_| Direction::Right),
+            map(tag("v"), |_| Direction::Down),
+            map(tag("<"), |_| Direction::Left),
+        )),
+    )))(input)
+    .map(|(_, ((x, y), _, direction))| Guard::new(Point(x, y), direction))
+    .map_err(|_| format!("Invalid guard '{input}', expected X,Y,HEADING with a heading of ^>v<"))
+}
+
 fn parse_tile(input: &str) -> IResult<&str, Tile> {
     alt((
         map(tag("#"), |_| Tile::Wall),
@@ -60,6 +79,16 @@ mod tests {
         assert_eq!(parse_tile("^").unwrap(), ("", Tile::Guard));
     }
 
+    #[test]
+    fn test_parse_guard() {
+        assert_eq!(
+            parse_guard("3,-4,<").unwrap(),
+            Guard::new(Point(3, -4), Direction::Left)
+        );
+        assert!(parse_guard("3,4").is_err());
+        assert!(parse_guard("3,4,x").is_err());
+    }
+
     #[test]
     fn test_parse_input() {
         let (_, (walls, _, _)) = parse_input(TEST_INPUT).unwrap();
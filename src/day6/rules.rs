@@ -0,0 +1,172 @@
+use crate::util::{Direction, Point};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Heading(u8);
+
+impl Heading {
+    const COUNT: u8 = 8;
+
+    pub fn offset(&self) -> Point {
+        [
+            Point(0, -1),
+            Point(1, -1),
+            Point(1, 0),
+            Point(1, 1),
+            Point(0, 1),
+            Point(-1, 1),
+            Point(-1, 0),
+            Point(-1, -1),
+        ][self.0 as usize]
+    }
+
+    pub fn to_direction(self) -> Option<Direction> {
+        match self.0 {
+            0 => Some(Direction::Up),
+            2 => Some(Direction::Right),
+            4 => Some(Direction::Down),
+            6 => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    fn rotate(self, steps: i8) -> Self {
+        Self((self.0 as i8 + steps).rem_euclid(Self::COUNT as i8) as u8)
+    }
+}
+
+impl From<Direction> for Heading {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => Self(0),
+            Direction::Right => Self(2),
+            Direction::Down => Self(4),
+            Direction::Left => Self(6),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Guard {
+    pub pos: Point,
+    pub heading: Heading,
+    turn_parity: bool,
+}
+
+impl Guard {
+    pub fn new(pos: Point, direction: Direction) -> Self {
+        Self::with_heading(pos, direction.into())
+    }
+
+    pub fn with_heading(pos: Point, heading: Heading) -> Self {
+        Self {
+            pos,
+            heading,
+            turn_parity: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Turn {
+    #[default]
+    Right,
+    Left,
+    Alternate,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Boundary {
+    #[default]
+    Exit,
+    Wrap,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Headings {
+    #[default]
+    Orthogonal,
+    Diagonal,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Rules {
+    pub turn: Turn,
+    pub boundary: Boundary,
+    pub headings: Headings,
+}
+
+impl Rules {
+    pub fn heading_count(&self) -> usize {
+        match self.headings {
+            Headings::Orthogonal => 4,
+            Headings::Diagonal => 8,
+        }
+    }
+
+    // Guard after rotating `steps` times away from an obstacle. All rotations of one turn go
+    // the same way, so `heading_count` of them try every heading; alternating rules switch
+    // sides for the next obstacle
+    pub fn turn(&self, guard: Guard, steps: u8) -> Guard {
+        let step = match self.headings {
+            Headings::Orthogonal => 2,
+            Headings::Diagonal => 1,
+        };
+
+        let turn_left = match self.turn {
+            Turn::Right => false,
+            Turn::Left => true,
+            Turn::Alternate => guard.turn_parity,
+        };
+
+        Guard {
+            pos: guard.pos,
+            heading: guard
+                .heading
+                .rotate(if turn_left { -step } else { step } * steps as i8),
+            turn_parity: (self.turn == Turn::Alternate) && !guard.turn_parity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading() {
+        let heading = Heading::from(Direction::Left);
+
+        assert_eq!(heading.offset(), Point(-1, 0));
+        assert_eq!(heading.to_direction(), Some(Direction::Left));
+        assert_eq!(heading.rotate(1).offset(), Point(-1, -1));
+        assert_eq!(heading.rotate(1).to_direction(), None);
+        assert_eq!(heading.rotate(2), Heading::from(Direction::Up));
+    }
+
+    #[test]
+    fn test_turn() {
+        let guard = Guard::new(Point(0, 0), Direction::Up);
+
+        let rules = Rules::default();
+        assert_eq!(rules.turn(guard, 1).heading, Direction::Right.into());
+        assert_eq!(rules.turn(guard, 3).heading, Direction::Left.into());
+
+        let rules = Rules {
+            turn: Turn::Left,
+            ..Rules::default()
+        };
+        assert_eq!(rules.turn(guard, 1).heading, Direction::Left.into());
+
+        let rules = Rules {
+            turn: Turn::Alternate,
+            headings: Headings::Diagonal,
+            ..Rules::default()
+        };
+        let once = rules.turn(guard, 1);
+        let twice = rules.turn(once, 1);
+        assert_eq!(once.heading.offset(), Point(1, -1));
+        assert_eq!(twice.heading, guard.heading);
+        assert_ne!(once, twice);
+        assert_eq!(rules.turn(once, 2).heading.offset(), Point(-1, -1));
+    }
+}
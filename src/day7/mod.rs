@@ -73,7 +73,7 @@ impl Equation {
                     }
                 }
 
-                if self.result % n == 0 {
+                if self.result.is_multiple_of(*n) {
                     let mul_eq = Equation {
                         result: self.result / n,
                         numbers: rest_numbers.to_vec(),
@@ -0,0 +1,647 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+};
+
+use super::{
+    disk::{Disk, DiskBlock},
+    free_tree::FreeTree,
+    strategy::Strategy,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub id: usize,
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Extent {
+    fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub id: usize,
+    pub from: usize,
+    pub to: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fragmentation {
+    pub file_count: usize,
+    pub fragmented_files: usize,
+    pub extent_count: usize,
+    pub used_blocks: usize,
+    pub free_blocks: usize,
+    pub free_segments: usize,
+    pub largest_free_segment: usize,
+}
+
+impl Fragmentation {
+    // Share of free space that is not part of the largest free segment
+    pub fn external(&self) -> f64 {
+        if self.free_blocks == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free_segment as f64 / self.free_blocks as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtentDisk {
+    size: usize,
+    // File runs keyed by their start block
+    extents: BTreeMap<usize, Extent>,
+    // Start blocks of every run belonging to a file id
+    files: BTreeMap<usize, BTreeSet<usize>>,
+    // Free segments keyed by start block, with their length
+    free: BTreeMap<usize, usize>,
+    // Start blocks of free segments, grouped by segment length
+    free_by_len: BTreeMap<usize, BTreeSet<usize>>,
+    // Free segment lengths by start block, for searches by position
+    free_tree: FreeTree,
+}
+
+impl ExtentDisk {
+    pub fn new(
+        size: usize,
+        extents: impl IntoIterator<Item = Extent>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut extents = extents
+            .into_iter()
+            .filter(|extent| extent.len > 0)
+            .collect::<Vec<_>>();
+        extents.sort_by_key(|extent| extent.start);
+
+        // Merged file runs and the free segments between them, indexed in bulk below
+        let mut runs: Vec<Extent> = Vec::new();
+        let mut gaps = Vec::new();
+        let mut position = 0;
+
+        for extent in extents {
+            if extent.start < position || extent.end() > size {
+                return Err(format!("Invalid extent: {:?}", extent).into());
+            }
+
+            match runs.last_mut() {
+                Some(run) if run.id == extent.id && run.end() == extent.start => {
+                    run.len += extent.len
+                }
+                _ => runs.push(extent),
+            }
+
+            if extent.start > position {
+                gaps.push((position, extent.start - position));
+            }
+            position = extent.end();
+        }
+
+        if size > position {
+            gaps.push((position, size - position));
+        }
+
+        let mut files: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for run in &runs {
+            files.entry(run.id).or_default().insert(run.start);
+        }
+
+        let mut free_by_len: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for &(start, len) in &gaps {
+            free_by_len.entry(len).or_default().insert(start);
+        }
+
+        Ok(Self {
+            size,
+            extents: runs.iter().map(|run| (run.start, *run)).collect(),
+            files,
+            free: gaps.iter().copied().collect(),
+            free_by_len,
+            free_tree: FreeTree::from_segments(size, gaps),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn extents(&self) -> impl Iterator<Item = &Extent> {
+        self.extents.values()
+    }
+
+    pub fn file_extents(&self, id: usize) -> impl Iterator<Item = Extent> + '_ {
+        self.files
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|start| self.extents[start])
+    }
+
+    pub fn file_len(&self, id: usize) -> usize {
+        self.file_extents(id).map(|extent| extent.len).sum()
+    }
+
+    pub fn file_ids(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.files.keys().copied()
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.extents()
+            .map(|extent| {
+                let (start, len) = (extent.start as u64, extent.len as u64);
+                extent.id as u64 * (len * start + len * (len - 1) / 2)
+            })
+            .sum()
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        Fragmentation {
+            file_count: self.files.len(),
+            fragmented_files: self.files.values().filter(|runs| runs.len() > 1).count(),
+            extent_count: self.extents.len(),
+            used_blocks: self.extents().map(|extent| extent.len).sum(),
+            free_blocks: self.free.values().sum(),
+            free_segments: self.free.len(),
+            largest_free_segment: self.free_by_len.keys().next_back().copied().unwrap_or(0),
+        }
+    }
+
+    // Moves whole files, highest id first, into the leftmost free segment that fits
+    pub fn compact_files(&mut self) {
+        self.compact(Strategy::LeftmostFit);
+    }
+
+    pub fn compact(&mut self, strategy: Strategy) {
+        self.compact_with(strategy, |_, _| {});
+    }
+
+    // Runs whole-file compaction, calling `on_step` after every file that moves
+    pub fn compact_with(&mut self, strategy: Strategy, mut on_step: impl FnMut(Move, &Self)) {
+        let ids = strategy.order(self.file_ids().collect());
+
+        for id in ids {
+            let runs = self.file_extents(id).collect::<Vec<_>>();
+            let (Some(&first), Some(&last)) = (runs.first(), runs.last()) else {
+                continue;
+            };
+            let len = runs.iter().map(|run| run.len).sum();
+
+            if let Some(to) = strategy.target(
+                &self.free_by_len,
+                &self.free_tree,
+                first.start,
+                last.end(),
+                len,
+            ) {
+                self.move_file(&runs, to);
+                on_step(
+                    Move {
+                        id,
+                        from: first.start,
+                        to,
+                        len,
+                    },
+                    self,
+                );
+            }
+        }
+    }
+
+    // Every layout of the disk during compaction, starting with the current one
+    pub fn render_steps(&self, strategy: Strategy) -> Vec<String> {
+        let mut disk = self.clone();
+        let mut frames = vec![disk.render()];
+
+        disk.compact_with(strategy, |_, disk| frames.push(disk.render()));
+
+        frames
+    }
+
+    // Layout in the puzzle's notation; ids above 9 only show their last digit
+    pub fn render(&self) -> String {
+        self.to_disk()
+            .blocks
+            .iter()
+            .map(|block| match block {
+                DiskBlock::Empty => '.',
+                DiskBlock::File(id) => char::from_digit((id % 10) as u32, 10).unwrap(),
+            })
+            .collect()
+    }
+
+    // Moves blocks one run at a time from the end of the disk into the leftmost gap
+    pub fn compact_blocks(&mut self) {
+        while let Some((&free_start, &free_len)) = self.free.first_key_value() {
+            let Some(&last) = self.extents.values().next_back() else {
+                break;
+            };
+
+            if free_start > last.start {
+                break;
+            }
+
+            let len = free_len.min(last.len);
+
+            self.remove_extent(last.start);
+            self.add_extent(Extent {
+                len: last.len - len,
+                ..last
+            });
+            self.take_free(free_start, len);
+            self.add_extent(Extent {
+                id: last.id,
+                start: free_start,
+                len,
+            });
+            self.add_free(last.end() - len, len);
+        }
+    }
+
+    pub fn delete_file(&mut self, id: usize) -> Result<(), Box<dyn Error>> {
+        let starts = self
+            .files
+            .get(&id)
+            .ok_or_else(|| format!("Unknown file: {}", id))?
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+
+        for start in starts {
+            let extent = self.remove_extent(start);
+            self.add_free(extent.start, extent.len);
+        }
+
+        Ok(())
+    }
+
+    // Adds a new file at the end of the disk and returns its id
+    pub fn append_file(&mut self, len: usize) -> Result<usize, Box<dyn Error>> {
+        if len == 0 {
+            return Err("Appended files need at least one block".into());
+        }
+
+        let id = self.files.keys().next_back().map_or(0, |id| id + 1);
+        let start = self.size;
+
+        self.size += len;
+        self.add_extent(Extent { id, start, len });
+
+        Ok(id)
+    }
+
+    pub fn to_disk(&self) -> Disk {
+        let mut blocks = vec![DiskBlock::Empty; self.size];
+
+        for extent in self.extents() {
+            blocks[extent.start..extent.end()].fill(DiskBlock::File(extent.id));
+        }
+
+        Disk::new(blocks)
+    }
+
+    // Relocates every run of a file into a single run at `target`
+    fn move_file(&mut self, runs: &[Extent], target: usize) {
+        let len = runs.iter().map(|run| run.len).sum();
+
+        self.take_free(target, len);
+
+        for run in runs {
+            self.remove_extent(run.start);
+            self.add_free(run.start, run.len);
+        }
+
+        self.add_extent(Extent {
+            id: runs[0].id,
+            start: target,
+            len,
+        });
+    }
+
+    fn add_extent(&mut self, mut extent: Extent) {
+        if extent.len == 0 {
+            return;
+        }
+
+        if let Some(&previous) = self
+            .extents
+            .range(..extent.start)
+            .next_back()
+            .map(|(_, e)| e)
+            .filter(|e| e.id == extent.id && e.end() == extent.start)
+        {
+            self.remove_extent(previous.start);
+            extent = Extent {
+                start: previous.start,
+                len: previous.len + extent.len,
+                ..extent
+            };
+        }
+
+        if let Some(&next) = self
+            .extents
+            .get(&extent.end())
+            .filter(|e| e.id == extent.id)
+        {
+            self.remove_extent(next.start);
+            extent.len += next.len;
+        }
+
+        self.extents.insert(extent.start, extent);
+        self.files
+            .entry(extent.id)
+            .or_default()
+            .insert(extent.start);
+    }
+
+    fn remove_extent(&mut self, start: usize) -> Extent {
+        let extent = self.extents.remove(&start).unwrap();
+        let runs = self.files.get_mut(&extent.id).unwrap();
+
+        runs.remove(&start);
+        if runs.is_empty() {
+            self.files.remove(&extent.id);
+        }
+
+        extent
+    }
+
+    fn add_free(&mut self, mut start: usize, mut len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        if let Some((&previous, &previous_len)) = self.free.range(..start).next_back() {
+            if previous + previous_len == start {
+                self.remove_free(previous);
+                start = previous;
+                len += previous_len;
+            }
+        }
+
+        if let Some(&next_len) = self.free.get(&(start + len)) {
+            self.remove_free(start + len);
+            len += next_len;
+        }
+
+        self.insert_free(start, len);
+    }
+
+    // Records a free segment whose neighbours are known not to be free
+    fn insert_free(&mut self, start: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        self.free.insert(start, len);
+        self.free_by_len.entry(len).or_default().insert(start);
+        self.free_tree.set(start, len);
+    }
+
+    fn remove_free(&mut self, start: usize) -> usize {
+        let len = self.free.remove(&start).unwrap();
+        self.free_tree.set(start, 0);
+
+        let starts = self.free_by_len.get_mut(&len).unwrap();
+
+        starts.remove(&start);
+        if starts.is_empty() {
+            self.free_by_len.remove(&len);
+        }
+
+        len
+    }
+
+    // Allocates `len` blocks at `start` out of the free segment containing it
+    fn take_free(&mut self, start: usize, len: usize) {
+        let (&segment_start, &segment_len) = self.free.range(..=start).next_back().unwrap();
+
+        self.remove_free(segment_start);
+        self.insert_free(segment_start, start - segment_start);
+        self.insert_free(start + len, segment_start + segment_len - start - len);
+    }
+}
+
+impl From<&Disk> for ExtentDisk {
+    fn from(disk: &Disk) -> Self {
+        let mut extents: Vec<Extent> = Vec::new();
+
+        for (start, &block) in disk.blocks.iter().enumerate() {
+            let DiskBlock::File(id) = block else {
+                continue;
+            };
+
+            match extents.last_mut() {
+                Some(extent) if extent.id == id && extent.end() == start => extent.len += 1,
+                _ => extents.push(Extent { id, start, len: 1 }),
+            }
+        }
+
+        Self::new(disk.blocks.len(), extents).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse::parse_disk;
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test-input.txt");
+
+    fn test_disk() -> ExtentDisk {
+        let (_, disk) = parse_disk(TEST_INPUT).unwrap();
+        ExtentDisk::from(&disk)
+    }
+
+    #[test]
+    fn test_from_disk() {
+        let (_, disk) = parse_disk(TEST_INPUT).unwrap();
+        let extents = ExtentDisk::from(&disk);
+
+        assert_eq!(extents.size(), 42);
+        assert_eq!(extents.extents().count(), 10);
+        assert_eq!(extents.to_disk().blocks, disk.blocks);
+        assert_eq!(extents.checksum(), disk.checksum());
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut disk = test_disk();
+        disk.compact_files();
+        assert_eq!(disk.checksum(), 2858);
+
+        let mut disk = test_disk();
+        disk.compact_blocks();
+        assert_eq!(disk.checksum(), 1928);
+
+        let (_, mut blocks) = parse_disk(TEST_INPUT).unwrap();
+        blocks.defragment();
+        assert_eq!(disk.to_disk().blocks, blocks.blocks);
+    }
+
+    #[test]
+    fn test_file_operations() {
+        let mut disk = test_disk();
+
+        disk.delete_file(1).unwrap();
+        assert_eq!(disk.file_len(1), 0);
+        assert_eq!(disk.fragmentation().free_segments, 7);
+        assert!(disk.delete_file(1).is_err());
+
+        assert!(disk.append_file(0).is_err());
+
+        let id = disk.append_file(4).unwrap();
+        assert_eq!(id, 10);
+        assert_eq!(disk.size(), 46);
+        assert_eq!(
+            disk.file_extents(id).collect::<Vec<_>>(),
+            vec![Extent {
+                id,
+                start: 42,
+                len: 4
+            }]
+        );
+
+        disk.compact_files();
+        assert_eq!(disk.file_extents(id).next().unwrap().start, 2);
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let disk = test_disk();
+        let stats = disk.fragmentation();
+
+        assert_eq!(stats.file_count, 10);
+        assert_eq!(stats.extent_count, 10);
+        assert_eq!(stats.used_blocks, 28);
+        assert_eq!(stats.free_blocks, 14);
+        assert_eq!(stats.free_segments, 8);
+        assert_eq!(stats.largest_free_segment, 3);
+
+        let mut disk = test_disk();
+        disk.compact_blocks();
+        let stats = disk.fragmentation();
+
+        assert_eq!(stats.fragmented_files, 2);
+        assert_eq!(stats.free_segments, 1);
+        assert_eq!(stats.external(), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_extents() {
+        let overlapping = [
+            Extent {
+                id: 0,
+                start: 0,
+                len: 2,
+            },
+            Extent {
+                id: 1,
+                start: 1,
+                len: 2,
+            },
+        ];
+
+        assert!(ExtentDisk::new(4, overlapping).is_err());
+        assert!(ExtentDisk::new(
+            1,
+            [Extent {
+                id: 0,
+                start: 0,
+                len: 2
+            }]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_render_steps() {
+        assert_eq!(
+            test_disk().render_steps(Strategy::LeftmostFit),
+            vec![
+                "00...111...2...333.44.5555.6666.777.888899",
+                "0099.111...2...333.44.5555.6666.777.8888..",
+                "0099.1117772...333.44.5555.6666.....8888..",
+                "0099.111777244.333....5555.6666.....8888..",
+                "00992111777.44.333....5555.6666.....8888..",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strategies() {
+        let disk = ExtentDisk::new(
+            10,
+            [
+                Extent {
+                    id: 0,
+                    start: 0,
+                    len: 1,
+                },
+                Extent {
+                    id: 1,
+                    start: 5,
+                    len: 1,
+                },
+                Extent {
+                    id: 2,
+                    start: 8,
+                    len: 2,
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(disk.render(), "0....1..22");
+
+        let compacted = |strategy| {
+            let mut disk = disk.clone();
+            disk.compact(strategy);
+            disk.render()
+        };
+
+        assert_eq!(compacted(Strategy::LeftmostFit), "0221......");
+        assert_eq!(compacted(Strategy::BestFit), "01....22..");
+        assert_eq!(compacted(Strategy::WorstFit), "0221......");
+        assert_eq!(compacted(Strategy::FirstFitById), "0122......");
+        assert_eq!(compacted(Strategy::TowardEnd), "......1022");
+    }
+
+    #[test]
+    fn test_step_callback() {
+        let mut disk = test_disk();
+        let mut moves = Vec::new();
+
+        disk.compact_with(Strategy::LeftmostFit, |step, disk| {
+            moves.push(step);
+            assert_eq!(disk.file_extents(step.id).next().unwrap().start, step.to);
+        });
+
+        assert_eq!(moves.len(), 4);
+        assert_eq!(
+            moves[0],
+            Move {
+                id: 9,
+                from: 40,
+                to: 2,
+                len: 2
+            }
+        );
+
+        for strategy in [
+            Strategy::BestFit,
+            Strategy::WorstFit,
+            Strategy::FirstFitById,
+            Strategy::TowardEnd,
+        ] {
+            let mut disk = test_disk();
+            disk.compact(strategy);
+
+            let stats = disk.fragmentation();
+            assert_eq!(stats.used_blocks, 28);
+            assert_eq!(stats.fragmented_files, 0);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Segment tree over block positions holding the length of the free segment that starts at
+// each block, so the leftmost or rightmost segment of some minimum length in a range of
+// starts is found in O(log n)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FreeTree {
+    // Maximum segment length per node, with the leaves in the second half
+    max_len: Vec<usize>,
+}
+
+impl FreeTree {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            max_len: vec![0; 2 * capacity.next_power_of_two()],
+        }
+    }
+
+    // Tree over `capacity` blocks holding the given `(start, len)` segments
+    pub fn from_segments(
+        capacity: usize,
+        segments: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Self {
+        let mut tree = Self::new(capacity);
+        let leaves = tree.capacity();
+
+        for (start, len) in segments {
+            tree.max_len[leaves + start] = len;
+        }
+
+        for node in (1..leaves).rev() {
+            tree.max_len[node] = tree.max_len[2 * node].max(tree.max_len[2 * node + 1]);
+        }
+
+        tree
+    }
+
+    pub fn set(&mut self, start: usize, len: usize) {
+        if start >= self.capacity() {
+            self.grow(start + 1);
+        }
+
+        let mut node = self.capacity() + start;
+        self.max_len[node] = len;
+
+        // Stop once an ancestor's maximum no longer changes
+        while node > 1 {
+            node /= 2;
+            let max_len = self.max_len[2 * node].max(self.max_len[2 * node + 1]);
+
+            if self.max_len[node] == max_len {
+                break;
+            }
+            self.max_len[node] = max_len;
+        }
+    }
+
+    pub fn len_at(&self, start: usize) -> usize {
+        self.max_len
+            .get(self.capacity() + start)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Leftmost start before `end` of a segment at least `len` blocks long
+    pub fn leftmost(&self, len: usize, end: usize) -> Option<usize> {
+        self.search(1, 0, self.capacity(), (0, end), len, false)
+    }
+
+    // Rightmost start from `start` on of a segment at least `len` blocks long
+    pub fn rightmost(&self, len: usize, start: usize) -> Option<usize> {
+        self.search(1, 0, self.capacity(), (start, self.capacity()), len, true)
+    }
+
+    fn capacity(&self) -> usize {
+        self.max_len.len() / 2
+    }
+
+    fn grow(&mut self, min_capacity: usize) {
+        let leaves = self.max_len.split_off(self.capacity());
+
+        *self = Self::new(min_capacity);
+
+        for (start, len) in leaves.into_iter().enumerate().filter(|&(_, len)| len > 0) {
+            self.set(start, len);
+        }
+    }
+
+    fn search(
+        &self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        (start, end): (usize, usize),
+        len: usize,
+        from_right: bool,
+    ) -> Option<usize> {
+        if node_end <= start || end <= node_start || self.max_len.get(node)? < &len {
+            return None;
+        }
+
+        if node_end - node_start == 1 {
+            return Some(node_start);
+        }
+
+        let middle = (node_start + node_end) / 2;
+        let halves = [
+            (2 * node, node_start, middle),
+            (2 * node + 1, middle, node_end),
+        ];
+        let [first, second] = if from_right {
+            [halves[1], halves[0]]
+        } else {
+            halves
+        };
+
+        self.search(first.0, first.1, first.2, (start, end), len, from_right)
+            .or_else(|| self.search(second.0, second.1, second.2, (start, end), len, from_right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut tree = FreeTree::default();
+        assert_eq!(tree.leftmost(1, 10), None);
+
+        tree.set(2, 3);
+        tree.set(9, 1);
+        tree.set(20, 4);
+        assert_eq!(tree, FreeTree::from_segments(21, [(2, 3), (9, 1), (20, 4)]));
+
+        assert_eq!(tree.leftmost(1, 30), Some(2));
+        assert_eq!(tree.leftmost(4, 30), Some(20));
+        assert_eq!(tree.leftmost(4, 20), None);
+        assert_eq!(tree.rightmost(1, 0), Some(20));
+        assert_eq!(tree.rightmost(2, 3), Some(20));
+        assert_eq!(tree.rightmost(2, 21), None);
+
+        assert_eq!(tree.len_at(20), 4);
+        assert_eq!(tree.len_at(21), 0);
+        assert_eq!(tree.len_at(100), 0);
+
+        tree.set(20, 0);
+        assert_eq!(tree.leftmost(4, 30), None);
+        assert_eq!(tree.rightmost(1, 3), Some(9));
+    }
+}
@@ -1,14 +1,50 @@
 mod disk;
+mod extents;
+mod free_tree;
 mod parse;
+pub mod strategy;
 
 use std::error::Error;
 
+use extents::{ExtentDisk, Fragmentation};
 use parse::parse_disk;
+use strategy::Strategy;
 
 use crate::Puzzle;
 
 const INPUT: &str = include_str!("input.txt");
 
+fn describe(stats: &Fragmentation) -> String {
+    format!(
+        "{} files ({} fragmented) in {} extents, {} used / {} free blocks in {} segments, external fragmentation {:.3}",
+        stats.file_count,
+        stats.fragmented_files,
+        stats.extent_count,
+        stats.used_blocks,
+        stats.free_blocks,
+        stats.free_segments,
+        stats.external()
+    )
+}
+
+// Fragmentation of the disk before and after `compact`
+fn report_input(
+    input: &str,
+    compact: impl FnOnce(&mut ExtentDisk),
+) -> Result<String, Box<dyn Error>> {
+    let (_, disk) = parse_disk(input).map_err(|e| e.to_owned())?;
+
+    let mut disk = ExtentDisk::from(&disk);
+    let before = disk.fragmentation();
+    compact(&mut disk);
+
+    Ok(format!(
+        "before: {}\nafter: {}",
+        describe(&before),
+        describe(&disk.fragmentation())
+    ))
+}
+
 pub struct Part1;
 
 impl Part1 {
@@ -25,23 +61,87 @@ impl Puzzle for Part1 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
         Self::solve_input(INPUT).map(|res| res.to_string())
     }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        report_input(INPUT, ExtentDisk::compact_blocks).map(Some)
+    }
 }
 
-pub struct Part2;
+#[derive(Default)]
+pub struct Part2 {
+    strategy: Strategy,
+}
 
 impl Part2 {
-    fn solve_input(input: &str) -> Result<u64, Box<dyn Error>> {
-        let (_, mut disk) = parse_disk(input).map_err(|e| e.to_owned())?;
+    pub fn with_strategy(strategy: Strategy) -> Self {
+        Self { strategy }
+    }
+
+    fn solve_input(&self, input: &str) -> Result<u64, Box<dyn Error>> {
+        let (_, disk) = parse_disk(input).map_err(|e| e.to_owned())?;
 
-        disk.defragment_v2();
+        let mut disk = ExtentDisk::from(&disk);
+        disk.compact(self.strategy);
 
         Ok(disk.checksum())
     }
+
+    // Fragmentation, the puzzle rule's checksum for comparison, and what happens when the
+    // largest file is rewritten at the end of the compacted disk
+    fn report_input(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let mut report = report_input(input, |disk| disk.compact(self.strategy))?;
+
+        let (_, disk) = parse_disk(input).map_err(|e| e.to_owned())?;
+        let mut disk = ExtentDisk::from(&disk);
+
+        let mut leftmost = disk.clone();
+        leftmost.compact_files();
+        report += &format!("\nleftmost fit checksum: {}", leftmost.checksum());
+
+        disk.compact(self.strategy);
+
+        let Some(id) = disk.file_ids().max_by_key(|&id| (disk.file_len(id), id)) else {
+            return Ok(report);
+        };
+        let len = disk.file_len(id);
+
+        disk.delete_file(id)?;
+        let new_id = disk.append_file(len)?;
+        let size = disk.size();
+        disk.compact(self.strategy);
+
+        let start = disk
+            .file_extents(new_id)
+            .next()
+            .map_or(size - len, |extent| extent.start);
+
+        report += &format!(
+            "\nrewriting file {id} ({len} blocks) as file {new_id} at the end of the {size}-block disk moves it to block {start}, checksum {}",
+            disk.checksum()
+        );
+
+        Ok(report)
+    }
 }
 
 impl Puzzle for Part2 {
     fn solve(&self) -> Result<String, Box<dyn Error>> {
-        Self::solve_input(INPUT).map(|res| res.to_string())
+        self.solve_input(INPUT).map(|res| res.to_string())
+    }
+
+    fn report(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.report_input(INPUT).map(Some)
+    }
+
+    // Layout in the puzzle's notation after every compaction step, one per line
+    fn image(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let (_, disk) = parse_disk(INPUT).map_err(|e| e.to_owned())?;
+
+        Ok(Some(
+            ExtentDisk::from(&disk)
+                .render_steps(self.strategy)
+                .join("\n"),
+        ))
     }
 }
 
@@ -71,6 +171,31 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        assert_eq!(Part2::solve_input(TEST_INPUT).unwrap(), 2858);
+        assert_eq!(Part2::default().solve_input(TEST_INPUT).unwrap(), 2858);
+    }
+
+    #[test]
+    fn test_report() {
+        let report = report_input(TEST_INPUT, |disk| disk.compact(Strategy::LeftmostFit)).unwrap();
+
+        assert_eq!(
+            report,
+            "before: 10 files (0 fragmented) in 10 extents, 28 used / 14 free blocks in 8 segments, external fragmentation 0.786\n\
+             after: 10 files (0 fragmented) in 10 extents, 28 used / 14 free blocks in 6 segments, external fragmentation 0.643"
+        );
+    }
+
+    #[test]
+    fn test_part2_report() {
+        let report = Part2::default().report_input(TEST_INPUT).unwrap();
+
+        assert_eq!(
+            report.lines().nth(2).unwrap(),
+            "leftmost fit checksum: 2858"
+        );
+        assert_eq!(
+            report.lines().nth(3).unwrap(),
+            "rewriting file 8 (4 blocks) as file 10 at the end of the 46-block disk moves it to block 18, checksum 2438"
+        );
     }
 }
@@ -19,8 +19,8 @@ pub fn parse_disk(input: &str) -> IResult<&str, Disk> {
                     _ => unreachable!(),
                 };
 
-                let files = std::iter::repeat(DiskBlock::File(id)).take(file_size as usize);
-                let free_space = std::iter::repeat(DiskBlock::Empty).take(free_size as usize);
+                let files = std::iter::repeat_n(DiskBlock::File(id), file_size as usize);
+                let free_space = std::iter::repeat_n(DiskBlock::Empty, free_size as usize);
 
                 files.chain(free_space)
             })
@@ -0,0 +1,61 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::free_tree::FreeTree;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Strategy {
+    // Highest id first, into the leftmost free segment that fits
+    #[default]
+    LeftmostFit,
+    // Highest id first, into the smallest free segment that fits
+    BestFit,
+    // Highest id first, into the largest free segment
+    WorstFit,
+    // Lowest id first, into the leftmost free segment that fits
+    FirstFitById,
+    // Lowest id first, to the end of the rightmost free segment that fits
+    TowardEnd,
+}
+
+impl Strategy {
+    pub fn order(&self, mut ids: Vec<usize>) -> Vec<usize> {
+        if matches!(
+            self,
+            Strategy::LeftmostFit | Strategy::BestFit | Strategy::WorstFit
+        ) {
+            ids.reverse();
+        }
+
+        ids
+    }
+
+    // Target block for a file of `len` blocks currently spanning `file_start..file_end`.
+    // Position-based strategies search `free_tree` in O(log n); size-based ones walk the
+    // free segment lengths from `len` up
+    pub fn target(
+        &self,
+        free_by_len: &BTreeMap<usize, BTreeSet<usize>>,
+        free_tree: &FreeTree,
+        file_start: usize,
+        file_end: usize,
+        len: usize,
+    ) -> Option<usize> {
+        let len = len.max(1);
+        let mut candidates = free_by_len.range(len..);
+
+        match self {
+            Strategy::LeftmostFit | Strategy::FirstFitById => free_tree.leftmost(len, file_start),
+            Strategy::BestFit => candidates
+                .find_map(|(_, starts)| starts.first().filter(|&&start| start < file_start))
+                .copied(),
+            Strategy::WorstFit => candidates
+                .rev()
+                .find_map(|(_, starts)| starts.first().filter(|&&start| start < file_start))
+                .copied(),
+            // Segments don't overlap, so the rightmost one that fits also ends furthest right
+            Strategy::TowardEnd => free_tree
+                .rightmost(len, file_end)
+                .map(|start| start + free_tree.len_at(start) - len),
+        }
+    }
+}
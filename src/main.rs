@@ -28,12 +28,22 @@ mod day23;
 mod day24;
 mod day25;
 
-use std::{collections::HashMap, time::Instant};
+use std::{collections::HashMap, path::PathBuf, time::Instant};
 
 use clap::Parser;
 
 pub trait Puzzle {
     fn solve(&self) -> Result<String, Box<dyn std::error::Error>>;
+
+    // Extra detail behind the answer, printed with `--report`
+    fn report(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(None)
+    }
+
+    // A picture of the puzzle, written to the file given with `--image`
+    fn image(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(None)
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -44,6 +54,100 @@ struct Args {
 
     #[arg(short, long)]
     part: Option<u64>,
+
+    #[arg(short, long)]
+    report: bool,
+
+    #[arg(short, long)]
+    image: Option<PathBuf>,
+
+    /// Day 13 token cost per button press, e.g. `--button-cost A=3`
+    #[arg(long = "button-cost", value_parser = day13::parse::parse_button_cost)]
+    button_costs: Vec<(String, u64)>,
+
+    /// Day 6 part 1 direction the guard turns at an obstacle
+    #[arg(long, value_enum, default_value_t)]
+    turn: day6::rules::Turn,
+
+    /// Day 6 part 1 what happens when the guard walks off the map
+    #[arg(long, value_enum, default_value_t)]
+    boundary: day6::rules::Boundary,
+
+    /// Day 6 part 1 directions the guard can face
+    #[arg(long, value_enum, default_value_t)]
+    headings: day6::rules::Headings,
+
+    /// Day 6 guard walking alongside the one on the map in the report, as X,Y,HEADING, e.g. `--guard 3,4,>`
+    #[arg(long = "guard", value_parser = day6::parse::parse_guard)]
+    guards: Vec<day6::rules::Guard>,
+
+    /// Day 9 part 2 rule for picking which file moves where
+    #[arg(long, value_enum, default_value_t)]
+    strategy: day9::strategy::Strategy,
+
+    /// Day 10 trail heights as START,END,STEP, e.g. `--trail-rules 9,0,-1`
+    #[arg(long, value_parser = day10::parse_rules)]
+    trail_rules: Option<day10::trails::TrailRules>,
+
+    /// Day 11 blink count, counted exactly or modulo `--modulus` through the transition graph
+    #[arg(long)]
+    blinks: Option<u64>,
+
+    /// Day 11 stone rules split by ';', e.g. `--stone-rules "n == 0 -> 1; * -> n * 2024"`
+    #[arg(long, value_parser = day11::parse::parse_rule_set)]
+    stone_rules: Option<day11::rules::RuleSet>,
+
+    /// Day 11 stone count and day 21 part 2 complexity are reported modulo this
+    #[arg(long)]
+    modulus: Option<u64>,
+
+    /// Day 21 part 2 count of directional keypads operated by robots
+    #[arg(long)]
+    robots: Option<u64>,
+
+    /// Day 21 door keypad, rows split by '/', e.g. `--keypad 123/456/789/#0A` or `...@0` to start on 0
+    #[arg(long, value_parser = day21::parse::parse_keypad)]
+    keypad: Option<day21::keypad::Keypad>,
+
+    /// Day 12 plots that count as touching
+    #[arg(long, value_enum, default_value_t)]
+    adjacency: day12::adjacency::Adjacency,
+
+    /// Day 12 plots whose letters differ only in case share a region
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Day 14 part 1 quadrant for robots on a middle row or column
+    #[arg(long, value_enum, default_value_t)]
+    centre_line: day14::quadrant::CentreLine,
+
+    /// Day 14 part 2 score for how much a round looks like a picture
+    #[arg(long, value_enum, default_value_t)]
+    metric: day14::analysis::Metric,
+
+    /// Day 15 part 2 map expansion per axis as WIDTHxHEIGHT, e.g. `--expand 3x2`
+    #[arg(long, value_parser = day15::parse::parse_expansion)]
+    expand: Option<(i64, i64)>,
+
+    /// Day 15 box corner the GPS score is measured to
+    #[arg(long, value_enum, default_value_t)]
+    anchor: day15::game::Anchor,
+
+    /// Day 15 whether robots push each other or block
+    #[arg(long, value_enum, default_value_t)]
+    robot_mode: day15::game::RobotMode,
+
+    /// Day 15 when each robot moves, as OFFSET+INTERVAL in robot order, e.g. `--timing 0+2`
+    #[arg(long = "timing", value_parser = day15::parse::parse_timing)]
+    timings: Vec<day15::schedule::Timing>,
+
+    /// Day 19 towel cost for the report, e.g. `--towel-cost gb=3`
+    #[arg(long = "towel-cost", value_parser = day19::parse::parse_towel_setting)]
+    towel_costs: Vec<(String, u64)>,
+
+    /// Day 19 towel stock for the report, e.g. `--towel-stock r=2`
+    #[arg(long = "towel-stock", value_parser = day19::parse::parse_towel_setting)]
+    towel_stocks: Vec<(String, u64)>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -66,7 +170,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     days.insert((5, 1), Box::new(day5::Part1));
     days.insert((5, 2), Box::new(day5::Part2));
 
-    days.insert((6, 1), Box::new(day6::Part1));
+    days.insert(
+        (6, 1),
+        Box::new(day6::Part1::with_rules(
+            args.turn,
+            args.boundary,
+            args.headings,
+            args.guards.clone(),
+        )),
+    );
     days.insert((6, 2), Box::new(day6::Part2));
 
     days.insert((7, 1), Box::new(day7::Part1));
@@ -76,25 +188,70 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     days.insert((8, 2), Box::new(day8::Part2));
 
     days.insert((9, 1), Box::new(day9::Part1));
-    days.insert((9, 2), Box::new(day9::Part2));
-
-    days.insert((10, 1), Box::new(day10::Part1));
-    days.insert((10, 2), Box::new(day10::Part2));
-
-    days.insert((11, 1), Box::new(day11::Part1));
-    days.insert((11, 2), Box::new(day11::Part2));
-
-    days.insert((12, 1), Box::new(day12::Part1));
-    days.insert((12, 2), Box::new(day12::Part2));
-
-    days.insert((13, 1), Box::new(day13::Part1));
-    days.insert((13, 2), Box::new(day13::Part2));
-
-    days.insert((14, 1), Box::new(day14::Part1));
-    days.insert((14, 2), Box::new(day14::Part2));
-
-    days.insert((15, 1), Box::new(day15::Part1));
-    days.insert((15, 2), Box::new(day15::Part2));
+    days.insert((9, 2), Box::new(day9::Part2::with_strategy(args.strategy)));
+
+    let trail_rules = args.trail_rules.unwrap_or_default();
+    days.insert((10, 1), Box::new(day10::Part1::with_rules(trail_rules)));
+    days.insert((10, 2), Box::new(day10::Part2::with_rules(trail_rules)));
+
+    days.insert(
+        (11, 1),
+        Box::new(day11::Part1::with_blinks(
+            args.stone_rules.clone(),
+            args.blinks,
+            args.modulus,
+        )),
+    );
+    days.insert(
+        (11, 2),
+        Box::new(day11::Part2::with_blinks(
+            args.stone_rules.clone(),
+            args.blinks,
+            args.modulus,
+        )),
+    );
+
+    days.insert(
+        (12, 1),
+        Box::new(day12::Part1::with_regions(args.adjacency, args.ignore_case)),
+    );
+    days.insert(
+        (12, 2),
+        Box::new(day12::Part2::with_regions(args.adjacency, args.ignore_case)),
+    );
+
+    days.insert(
+        (13, 1),
+        Box::new(day13::Part1::with_button_costs(args.button_costs.clone())),
+    );
+    days.insert(
+        (13, 2),
+        Box::new(day13::Part2::with_button_costs(args.button_costs.clone())),
+    );
+
+    days.insert(
+        (14, 1),
+        Box::new(day14::Part1::with_centre_line(args.centre_line)),
+    );
+    days.insert((14, 2), Box::new(day14::Part2::with_metric(args.metric)));
+
+    days.insert(
+        (15, 1),
+        Box::new(day15::Part1::with_settings(
+            args.anchor,
+            args.robot_mode,
+            args.timings.clone(),
+        )),
+    );
+    days.insert(
+        (15, 2),
+        Box::new(day15::Part2::with_settings(
+            args.expand,
+            args.anchor,
+            args.robot_mode,
+            args.timings.clone(),
+        )),
+    );
 
     days.insert((16, 1), Box::new(day16::Part1));
     days.insert((16, 2), Box::new(day16::Part2));
@@ -105,14 +262,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     days.insert((18, 1), Box::new(day18::Part1));
     days.insert((18, 2), Box::new(day18::Part2));
 
-    days.insert((19, 1), Box::new(day19::Part1));
+    days.insert(
+        (19, 1),
+        Box::new(day19::Part1::with_towels(
+            args.towel_costs.clone(),
+            args.towel_stocks.clone(),
+        )),
+    );
     days.insert((19, 2), Box::new(day19::Part2));
 
     days.insert((20, 1), Box::new(day20::Part1));
     days.insert((20, 2), Box::new(day20::Part2));
 
-    days.insert((21, 1), Box::new(day21::Part1));
-    days.insert((21, 2), Box::new(day21::Part2));
+    days.insert(
+        (21, 1),
+        Box::new(day21::Part1::with_door(args.keypad.clone())),
+    );
+    days.insert(
+        (21, 2),
+        Box::new(day21::Part2::with_robots(
+            args.keypad.clone(),
+            args.robots,
+            args.modulus,
+        )),
+    );
 
     days.insert((22, 1), Box::new(day22::Part1));
     days.insert((22, 2), Box::new(day22::Part2));
@@ -152,6 +325,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         println!();
+
+        if args.report {
+            if let Some(report) = puzzle.report()? {
+                println!("{report}");
+            }
+        }
+
+        if let Some(path) = &args.image {
+            if let Some(image) = puzzle.image()? {
+                std::fs::write(path, image)?;
+            }
+        }
     }
 
     Ok(())
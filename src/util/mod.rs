@@ -213,6 +213,19 @@ impl PointRange {
     pub fn new(start: Point, end: Point) -> Self {
         Self { start, end }
     }
+
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn end(&self) -> Point {
+        self.end
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (self.start.1..self.end.1)
+            .flat_map(move |y| (self.start.0..self.end.0).map(move |x| Point(x, y)))
+    }
 }
 
 #[cfg(test)]
@@ -230,6 +243,18 @@ mod tests {
         assert_eq!(p.wrap(Point(10, 10)), Point(5, 4));
     }
 
+    #[test]
+    fn test_point_range() {
+        let range = PointRange::new(Point(1, 1), Point(3, 2));
+
+        assert!(range.contains(Point(2, 1)));
+        assert!(!range.contains(Point(3, 1)));
+        assert_eq!(
+            range.points().collect::<Vec<_>>(),
+            vec![Point(1, 1), Point(2, 1)]
+        );
+    }
+
     #[test]
     fn test_direction() {
         let d = Direction::Up;